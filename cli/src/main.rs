@@ -0,0 +1,282 @@
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use anchor_lang::Discriminator;
+use anyhow::{anyhow, Result};
+use clap::{Parser, Subcommand};
+use solana_sdk::hash::Hash;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{read_keypair_file, Keypair, Signer};
+use solana_sdk::transaction::Transaction;
+
+use gso::GSOState;
+use gso_client::*;
+
+mod params;
+mod rpc;
+mod show;
+
+use crate::params::ConfigParams;
+use crate::rpc::RpcClient;
+
+#[derive(Parser)]
+#[clap(name = "gso", about = "Admin CLI for GSO")]
+struct Cli {
+    #[clap(long, default_value = "https://api.mainnet-beta.solana.com")]
+    url: String,
+
+    /// Defaults to the solana CLI keypair.
+    #[clap(long)]
+    keypair: Option<PathBuf>,
+
+    /// Authority for the transaction when it is not the keypair, for example
+    /// a multisig. Only valid with --sign-only.
+    #[clap(long)]
+    authority: Option<String>,
+
+    /// Print the transaction as base64 instead of sending it. It is signed by
+    /// the keypair when that is the authority.
+    #[clap(long)]
+    sign_only: bool,
+
+    /// Blockhash to use instead of fetching one, for offline signing.
+    #[clap(long)]
+    blockhash: Option<String>,
+
+    #[clap(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Configure a period from a TOML or JSON params file.
+    Config {
+        params: PathBuf,
+        /// Also create the Project account, for the first period or the first
+        /// one after GSOs from before projects.
+        #[clap(long)]
+        init_project: bool,
+    },
+    /// Configure a period with a lockup mint different from the SO base mint.
+    ConfigV2 {
+        params: PathBuf,
+        #[clap(long)]
+        init_project: bool,
+    },
+    /// Name the option and xToken mints.
+    NameTokens {
+        project_name: String,
+        period_num: u64,
+    },
+    /// Withdraw the tokens left in the SO vault after expiration.
+    Withdraw {
+        project_name: String,
+        period_num: u64,
+        /// Token account that receives the tokens.
+        destination: String,
+    },
+    /// Store the lockup mint on a GSO configured before it was recorded.
+    MigrateLockupMint {
+        project_name: String,
+        period_num: u64,
+    },
+    /// Print the decoded GSOState with its derived addresses.
+    Show {
+        project_name: String,
+        period_num: u64,
+    },
+    /// Lists every GSO, or only the periods of one project.
+    List { project_name: Option<String> },
+    /// Close the GSOState and base vaults once everything is unstaked.
+    Close {
+        project_name: String,
+        period_num: u64,
+    },
+}
+
+fn main() -> Result<()> {
+    let cli: Cli = Cli::parse();
+    let rpc_client: RpcClient = RpcClient::new(&cli.url);
+
+    let instructions: Vec<Instruction> = match &cli.command {
+        Command::Config {
+            params,
+            init_project,
+        } => {
+            let params: ConfigParams = ConfigParams::from_file(params)?;
+            let authority: Pubkey = authority(&cli)?;
+            let mut instructions: Vec<Instruction> =
+                init_project_instructions(&params, &authority, *init_project);
+            instructions.push(instruction::config(
+                &params.accounts(authority),
+                params.config_args(),
+            ));
+            instructions
+        }
+        Command::ConfigV2 {
+            params,
+            init_project,
+        } => {
+            let params: ConfigParams = ConfigParams::from_file(params)?;
+            let authority: Pubkey = authority(&cli)?;
+            let lockup_mint: Pubkey = params
+                .lockup_mint
+                .ok_or_else(|| anyhow!("config-v2 needs lockup_mint in the params"))?;
+            let mut instructions: Vec<Instruction> =
+                init_project_instructions(&params, &authority, *init_project);
+            instructions.push(instruction::config_v2(
+                &params.accounts(authority),
+                &lockup_mint,
+                params.config_v2_args(),
+            ));
+            instructions
+        }
+        Command::NameTokens {
+            project_name,
+            period_num,
+        } => {
+            let state: GSOState = fetch_state(&rpc_client, project_name, *period_num)?;
+            vec![instruction::name_tokens(&state, &authority(&cli)?)]
+        }
+        Command::Withdraw {
+            project_name,
+            period_num,
+            destination,
+        } => {
+            let state: GSOState = fetch_state(&rpc_client, project_name, *period_num)?;
+            vec![instruction::withdraw(
+                &state,
+                &authority(&cli)?,
+                &Pubkey::from_str(destination)?,
+            )]
+        }
+        Command::Close {
+            project_name,
+            period_num,
+        } => {
+            let state: GSOState = fetch_state(&rpc_client, project_name, *period_num)?;
+            vec![instruction::close(&state, &authority(&cli)?)]
+        }
+        Command::MigrateLockupMint {
+            project_name,
+            period_num,
+        } => {
+            let state: GSOState = fetch_state(&rpc_client, project_name, *period_num)?;
+            vec![instruction::migrate_lockup_mint(&state)]
+        }
+        Command::Show {
+            project_name,
+            period_num,
+        } => {
+            let address: Pubkey = find_gso_state_address(*period_num, project_name).0;
+            let state: GSOState = fetch_gso_state(&rpc_client, &address)?;
+            show::print_gso_state(&address, &state);
+            return Ok(());
+        }
+        Command::List { project_name } => {
+            let mut states: Vec<(Pubkey, GSOState)> = rpc_client
+                .get_program_accounts(&gso::ID, &GSOState::discriminator())?
+                .into_iter()
+                .filter_map(|(address, data)| {
+                    decode_gso_state(&data).ok().map(|state| (address, state))
+                })
+                .filter(|(_address, state)| match project_name {
+                    Some(project_name) => &state.project_name == project_name,
+                    None => true,
+                })
+                .collect();
+            states.sort_by(|(_, a), (_, b)| {
+                (&a.project_name, a.period_num).cmp(&(&b.project_name, b.period_num))
+            });
+            for (address, state) in states.iter() {
+                println!(
+                    "{}  {} period {}  lockup end {}",
+                    address,
+                    state.project_name,
+                    state.period_num,
+                    show::format_timestamp(state.lockup_period_end)
+                );
+            }
+            return Ok(());
+        }
+    };
+
+    send(&cli, &rpc_client, &instructions)
+}
+
+fn keypair(cli: &Cli) -> Result<Keypair> {
+    let path: PathBuf = match &cli.keypair {
+        Some(path) => path.clone(),
+        None => {
+            let home: String = std::env::var("HOME")?;
+            PathBuf::from(home).join(".config/solana/id.json")
+        }
+    };
+    read_keypair_file(&path)
+        .map_err(|err| anyhow!("Failed to read keypair {}: {}", path.display(), err))
+}
+
+fn authority(cli: &Cli) -> Result<Pubkey> {
+    match &cli.authority {
+        Some(authority) => {
+            if !cli.sign_only {
+                return Err(anyhow!("--authority can only be used with --sign-only"));
+            }
+            Ok(Pubkey::from_str(authority)?)
+        }
+        None => Ok(keypair(cli)?.pubkey()),
+    }
+}
+
+fn init_project_instructions(
+    params: &ConfigParams,
+    authority: &Pubkey,
+    init_project: bool,
+) -> Vec<Instruction> {
+    if !init_project {
+        return Vec::new();
+    }
+    // Periods are configured in order, so the one before this is the latest.
+    vec![instruction::init_project(
+        authority,
+        &params.project_name,
+        params.period_num.saturating_sub(1),
+        params.lockup_ratio_tokens_per_million,
+        params.lot_size,
+        params.strike_price,
+    )]
+}
+
+fn fetch_state(rpc_client: &RpcClient, project_name: &str, period_num: u64) -> Result<GSOState> {
+    fetch_gso_state(
+        rpc_client,
+        &find_gso_state_address(period_num, project_name).0,
+    )
+}
+
+fn send(cli: &Cli, rpc_client: &RpcClient, instructions: &[Instruction]) -> Result<()> {
+    let authority: Pubkey = authority(cli)?;
+    let blockhash: Hash = match &cli.blockhash {
+        Some(blockhash) => Hash::from_str(blockhash)?,
+        None => rpc_client.get_latest_blockhash()?,
+    };
+    let mut transaction: Transaction = Transaction::new_with_payer(instructions, Some(&authority));
+
+    if cli.sign_only {
+        // The authority may be a multisig that signs elsewhere, so only sign
+        // when the keypair is the authority.
+        if cli.authority.is_none() {
+            transaction.try_partial_sign(&[&keypair(cli)?], blockhash)?;
+        } else {
+            transaction.message.recent_blockhash = blockhash;
+        }
+        println!("{}", base64::encode(bincode::serialize(&transaction)?));
+        return Ok(());
+    }
+
+    transaction.try_sign(&[&keypair(cli)?], blockhash)?;
+    let signature: String = rpc_client.send_and_confirm_transaction(&transaction)?;
+    println!("{}", signature);
+    Ok(())
+}
@@ -0,0 +1,217 @@
+use std::path::Path;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Deserializer};
+use solana_sdk::pubkey::Pubkey;
+
+use gso_client::instruction::ConfigAccounts;
+
+// Parameters for config and config_v2, read from a TOML or JSON file. Field
+// names are the same as the config arguments. so_authority_bump is derived so
+// it is not in the file. Everything after project_name is optional and
+// defaults to off.
+#[derive(Deserialize, Debug)]
+pub struct ConfigParams {
+    pub period_num: u64,
+    pub lockup_ratio_tokens_per_million: u64,
+    pub lockup_period_end: u64,
+    pub option_expiration: u64,
+    pub subscription_period_end: u64,
+    pub lot_size: u64,
+    pub num_tokens: u64,
+    pub project_name: String,
+    pub strike_price: u64,
+
+    #[serde(default)]
+    pub max_total_locked: u64,
+    #[serde(default)]
+    pub max_per_wallet: u64,
+    #[serde(default)]
+    pub subscription_period_start: u64,
+    #[serde(default)]
+    pub lockup_ratio_schedule: u8,
+    #[serde(default)]
+    pub lockup_ratio_end_tokens_per_million: u64,
+    #[serde(default)]
+    pub lockup_ratio_steps: u64,
+    #[serde(default)]
+    pub max_slash_bps: u64,
+    #[serde(default)]
+    pub slash_timelock: u64,
+    #[serde(default, deserialize_with = "pubkey_from_str")]
+    pub slash_destination: Pubkey,
+    #[serde(default)]
+    pub credit_limit: u64,
+    #[serde(default)]
+    pub vesting_end: u64,
+    #[serde(default)]
+    pub early_unstake_penalty_bps: u64,
+    #[serde(default)]
+    pub early_unstake_penalty_decay: bool,
+    #[serde(default, deserialize_with = "pubkey_from_str")]
+    pub early_unstake_penalty_destination: Pubkey,
+    #[serde(default)]
+    pub lockup_ratio_num: u64,
+    #[serde(default)]
+    pub lockup_ratio_den: u64,
+
+    // Accounts
+    #[serde(deserialize_with = "pubkey_from_str")]
+    pub so_base_account: Pubkey,
+    #[serde(deserialize_with = "pubkey_from_str")]
+    pub so_quote_account: Pubkey,
+    #[serde(deserialize_with = "pubkey_from_str")]
+    pub so_base_mint: Pubkey,
+    #[serde(deserialize_with = "pubkey_from_str")]
+    pub so_quote_mint: Pubkey,
+    // Only used by config-v2.
+    #[serde(default, deserialize_with = "option_pubkey_from_str")]
+    pub lockup_mint: Option<Pubkey>,
+}
+
+fn pubkey_from_str<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Pubkey, D::Error> {
+    let address: String = String::deserialize(deserializer)?;
+    Pubkey::from_str(&address).map_err(serde::de::Error::custom)
+}
+
+fn option_pubkey_from_str<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Pubkey>, D::Error> {
+    pubkey_from_str(deserializer).map(Some)
+}
+
+impl ConfigParams {
+    // Reads TOML or JSON depending on the file extension.
+    pub fn from_file(path: &Path) -> Result<ConfigParams> {
+        let contents: String = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("toml") => ConfigParams::from_toml(&contents),
+            Some("json") => ConfigParams::from_json(&contents),
+            _ => Err(anyhow!("Params file must be .toml or .json")),
+        }
+    }
+
+    pub fn from_toml(contents: &str) -> Result<ConfigParams> {
+        Ok(toml::from_str(contents)?)
+    }
+
+    pub fn from_json(contents: &str) -> Result<ConfigParams> {
+        Ok(serde_json::from_str(contents)?)
+    }
+
+    pub fn accounts(&self, authority: Pubkey) -> ConfigAccounts {
+        ConfigAccounts {
+            authority,
+            so_base_account: self.so_base_account,
+            so_quote_account: self.so_quote_account,
+            so_base_mint: self.so_base_mint,
+            so_quote_mint: self.so_quote_mint,
+        }
+    }
+
+    pub fn options(&self) -> gso::ConfigOptions {
+        gso::ConfigOptions {
+            max_total_locked: self.max_total_locked,
+            max_per_wallet: self.max_per_wallet,
+            subscription_period_start: self.subscription_period_start,
+            lockup_ratio_schedule: self.lockup_ratio_schedule,
+            lockup_ratio_end_tokens_per_million: self.lockup_ratio_end_tokens_per_million,
+            lockup_ratio_steps: self.lockup_ratio_steps,
+            max_slash_bps: self.max_slash_bps,
+            slash_timelock: self.slash_timelock,
+            slash_destination: self.slash_destination,
+            credit_limit: self.credit_limit,
+            vesting_end: self.vesting_end,
+            early_unstake_penalty_bps: self.early_unstake_penalty_bps,
+            early_unstake_penalty_decay: self.early_unstake_penalty_decay,
+            early_unstake_penalty_destination: self.early_unstake_penalty_destination,
+            lockup_ratio_num: self.lockup_ratio_num,
+            lockup_ratio_den: self.lockup_ratio_den,
+        }
+    }
+
+    pub fn config_args(&self) -> gso::instruction::Config {
+        gso::instruction::Config {
+            period_num: self.period_num,
+            lockup_ratio_tokens_per_million: self.lockup_ratio_tokens_per_million,
+            lockup_period_end: self.lockup_period_end,
+            option_expiration: self.option_expiration,
+            subscription_period_end: self.subscription_period_end,
+            lot_size: self.lot_size,
+            num_tokens: self.num_tokens,
+            project_name: self.project_name.clone(),
+            strike_price: self.strike_price,
+            so_authority_bump: 0,
+            options: self.options(),
+        }
+    }
+
+    pub fn config_v2_args(&self) -> gso::instruction::ConfigV2 {
+        gso::instruction::ConfigV2 {
+            period_num: self.period_num,
+            lockup_ratio_tokens_per_million: self.lockup_ratio_tokens_per_million,
+            lockup_period_end: self.lockup_period_end,
+            option_expiration: self.option_expiration,
+            subscription_period_end: self.subscription_period_end,
+            lot_size: self.lot_size,
+            num_tokens: self.num_tokens,
+            project_name: self.project_name.clone(),
+            strike_price: self.strike_price,
+            so_authority_bump: 0,
+            options: self.options(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE_TOML: &str = include_str!("../config.example.toml");
+
+    #[test]
+    fn test_example_toml() {
+        let params: ConfigParams = ConfigParams::from_toml(EXAMPLE_TOML).unwrap();
+        assert_eq!(params.period_num, 1);
+        assert_eq!(params.project_name, "EXAMPLE");
+        assert_eq!(params.lockup_ratio_tokens_per_million, 200_000);
+        assert_eq!(params.max_slash_bps, 0);
+        assert_eq!(params.slash_destination, Pubkey::default());
+        assert_eq!(params.lockup_mint, None);
+    }
+
+    #[test]
+    fn test_json_matches_toml() {
+        let json: &str = r#"{
+            "period_num": 1,
+            "lockup_ratio_tokens_per_million": 200000,
+            "lockup_period_end": 1700000000,
+            "option_expiration": 1700000000,
+            "subscription_period_end": 1690000000,
+            "lot_size": 1000000,
+            "num_tokens": 1000000000000,
+            "project_name": "EXAMPLE",
+            "strike_price": 50000,
+            "so_base_account": "11111111111111111111111111111112",
+            "so_quote_account": "11111111111111111111111111111113",
+            "so_base_mint": "11111111111111111111111111111114",
+            "so_quote_mint": "11111111111111111111111111111115",
+            "lockup_mint": "11111111111111111111111111111116"
+        }"#;
+        let from_json: ConfigParams = ConfigParams::from_json(json).unwrap();
+        let from_toml: ConfigParams = ConfigParams::from_toml(EXAMPLE_TOML).unwrap();
+        assert_eq!(from_json.lockup_period_end, from_toml.lockup_period_end);
+        assert_eq!(from_json.so_base_mint, from_toml.so_base_mint);
+        assert_eq!(
+            from_json.lockup_mint,
+            Some(Pubkey::from_str("11111111111111111111111111111116").unwrap())
+        );
+    }
+
+    #[test]
+    fn test_missing_required_field() {
+        assert!(ConfigParams::from_toml("period_num = 1").is_err());
+    }
+}
@@ -0,0 +1,141 @@
+use std::str::FromStr;
+use std::thread::sleep;
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use serde_json::{json, Value};
+use solana_sdk::hash::Hash;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::transaction::Transaction;
+
+use gso_client::AccountFetcher;
+
+// Minimal JSON RPC client with just the calls the CLI needs. The
+// solana-client crate pulls in hardware wallet support that needs system
+// libraries to build, which is not worth it for a handful of calls.
+pub struct RpcClient {
+    url: String,
+}
+
+impl RpcClient {
+    pub fn new(url: &str) -> RpcClient {
+        RpcClient {
+            url: url.to_string(),
+        }
+    }
+
+    fn call(&self, method: &str, params: Value) -> Result<Value> {
+        let response: Value = ureq::post(&self.url)
+            .send_json(json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": method,
+                "params": params,
+            }))?
+            .into_json()?;
+        if let Some(error) = response.get("error") {
+            return Err(anyhow!("{} failed: {}", method, error));
+        }
+        Ok(response["result"].clone())
+    }
+
+    pub fn get_latest_blockhash(&self) -> Result<Hash> {
+        let result: Value = self.call("getLatestBlockhash", json!([]))?;
+        let blockhash: &str = result["value"]["blockhash"]
+            .as_str()
+            .ok_or_else(|| anyhow!("Missing blockhash"))?;
+        Ok(Hash::from_str(blockhash)?)
+    }
+
+    pub fn get_account_data(&self, address: &Pubkey) -> Result<Vec<u8>> {
+        let result: Value = self.call(
+            "getAccountInfo",
+            json!([address.to_string(), {"encoding": "base64"}]),
+        )?;
+        if result["value"].is_null() {
+            return Err(anyhow!("Account {} not found", address));
+        }
+        decode_data(&result["value"]["data"])
+    }
+
+    // Accounts of the program that start with the anchor discriminator.
+    pub fn get_program_accounts(
+        &self,
+        program_id: &Pubkey,
+        discriminator: &[u8],
+    ) -> Result<Vec<(Pubkey, Vec<u8>)>> {
+        let result: Value = self.call(
+            "getProgramAccounts",
+            json!([
+                program_id.to_string(),
+                {
+                    "encoding": "base64",
+                    "filters": [{"memcmp": {"offset": 0, "bytes": bs58::encode(discriminator).into_string()}}],
+                }
+            ]),
+        )?;
+        let accounts: &Vec<Value> = result
+            .as_array()
+            .ok_or_else(|| anyhow!("Unexpected getProgramAccounts result"))?;
+        accounts
+            .iter()
+            .map(|account| {
+                let address: &str = account["pubkey"]
+                    .as_str()
+                    .ok_or_else(|| anyhow!("Missing pubkey"))?;
+                Ok((
+                    Pubkey::from_str(address)?,
+                    decode_data(&account["account"]["data"])?,
+                ))
+            })
+            .collect()
+    }
+
+    // Sends the transaction and waits for it to be confirmed.
+    pub fn send_and_confirm_transaction(&self, transaction: &Transaction) -> Result<String> {
+        let serialized: Vec<u8> = bincode::serialize(transaction)?;
+        let signature: Value = self.call(
+            "sendTransaction",
+            json!([base64::encode(serialized), {"encoding": "base64"}]),
+        )?;
+        let signature: String = signature
+            .as_str()
+            .ok_or_else(|| anyhow!("Missing signature"))?
+            .to_string();
+
+        for _ in 0..60 {
+            let statuses: Value = self.call("getSignatureStatuses", json!([[signature]]))?;
+            let status: &Value = &statuses["value"][0];
+            if !status.is_null() {
+                if !status["err"].is_null() {
+                    return Err(anyhow!(
+                        "Transaction {} failed: {}",
+                        signature,
+                        status["err"]
+                    ));
+                }
+                if status["confirmationStatus"] != "processed" {
+                    return Ok(signature);
+                }
+            }
+            sleep(Duration::from_millis(500));
+        }
+        Err(anyhow!("Transaction {} was not confirmed", signature))
+    }
+}
+
+impl AccountFetcher for RpcClient {
+    type Error = anyhow::Error;
+
+    fn get_account_data(&self, address: &Pubkey) -> Result<Vec<u8>> {
+        RpcClient::get_account_data(self, address)
+    }
+}
+
+// Account data comes back as [data, encoding].
+fn decode_data(data: &Value) -> Result<Vec<u8>> {
+    let encoded: &str = data[0]
+        .as_str()
+        .ok_or_else(|| anyhow!("Unexpected account data"))?;
+    Ok(base64::decode(encoded)?)
+}
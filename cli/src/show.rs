@@ -0,0 +1,138 @@
+use chrono::DateTime;
+use solana_sdk::pubkey::Pubkey;
+
+use gso::GSOState;
+use gso_client::*;
+
+// Unix timestamp as a UTC date. 0 is used for unset times.
+pub fn format_timestamp(timestamp: u64) -> String {
+    if timestamp == 0 {
+        return "unset".to_string();
+    }
+    match DateTime::from_timestamp(timestamp as i64, 0) {
+        Some(date_time) => format!(
+            "{} ({})",
+            date_time.format("%Y-%m-%d %H:%M:%S UTC"),
+            timestamp
+        ),
+        None => timestamp.to_string(),
+    }
+}
+
+pub fn print_gso_state(address: &Pubkey, state: &GSOState) {
+    println!("GSO State                 {}", address);
+    println!("Project                   {}", state.project_name);
+    println!("Period                    {}", state.period_num);
+    println!("Authority                 {}", state.authority);
+    if state.pending_authority != Pubkey::default() {
+        println!("Pending authority         {}", state.pending_authority);
+    }
+    println!("Option base mint          {}", state.option_base_mint);
+    if state.lockup_mint == Pubkey::default() {
+        println!("Lockup mint               not migrated");
+    } else {
+        println!("Lockup mint               {}", state.lockup_mint);
+    }
+    println!(
+        "Decimals                  lockup {} option base {}",
+        state.lockup_decimals, state.option_base_decimals
+    );
+    println!("SO state                  {}", state.staking_options_state);
+    println!("Paused                    {:#05b}", state.paused);
+    println!();
+    println!(
+        "Subscription start        {}",
+        format_timestamp(state.subscription_period_start)
+    );
+    println!(
+        "Subscription end          {}",
+        format_timestamp(state.subscription_period_end)
+    );
+    println!(
+        "Lockup end                {}",
+        format_timestamp(state.lockup_period_end)
+    );
+    println!(
+        "Vesting end               {}",
+        format_timestamp(state.vesting_end)
+    );
+    println!();
+    println!(
+        "Lockup ratio per million  {}",
+        state.lockup_ratio_tokens_per_million
+    );
+    if state.lockup_ratio_den > 0 {
+        println!(
+            "Exact lockup ratio        {}/{}",
+            state.lockup_ratio_num, state.lockup_ratio_den
+        );
+    }
+    println!("Ratio schedule            {}", state.lockup_ratio_schedule);
+    println!("Strike                    {}", state.strike);
+    println!("Total locked              {}", state.total_locked);
+    println!("Max total locked          {}", state.max_total_locked);
+    println!("Max per wallet            {}", state.max_per_wallet);
+    println!("Max slash bps             {}", state.max_slash_bps);
+    println!("Total slashed             {}", state.total_slashed);
+    println!("Credit limit              {}", state.credit_limit);
+    println!("Outstanding debt          {}", state.outstanding_debt);
+    println!("Defaulted                 {}", state.defaulted);
+    println!(
+        "Early unstake penalty bps {}",
+        state.early_unstake_penalty_bps
+    );
+
+    println!();
+    println!(
+        "Project account           {}",
+        find_project_address(&state.project_name).0
+    );
+    println!(
+        "SO authority              {}",
+        find_so_authority_address(address).0
+    );
+    println!(
+        "SO base vault             {}",
+        find_so_base_vault_address(&state.staking_options_name(), &state.option_base_mint).0
+    );
+    for tier in 0..=state.tiers.len() {
+        let tier: u8 = tier as u8;
+        println!();
+        println!(
+            "Tier {}  lockup end       {}",
+            tier,
+            format_timestamp(state.tier_lockup_period_end(tier))
+        );
+        println!(
+            "        xToken mint       {}",
+            find_x_base_mint_address(address, tier).0
+        );
+        println!(
+            "        base vault        {}",
+            find_base_vault_address(address, tier).0
+        );
+    }
+    for strike_index in 0..=state.strikes.len() {
+        let strike: u64 = state.strike_at(strike_index as u8);
+        println!();
+        println!("Strike {}  strike         {}", strike_index, strike);
+        println!(
+            "          option mint    {}",
+            find_so_option_mint_address(&state.staking_options_state, strike).0
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(0), "unset");
+        assert_eq!(
+            format_timestamp(1_700_000_000),
+            "2023-11-14 22:13:20 UTC (1700000000)"
+        );
+    }
+}
@@ -0,0 +1,803 @@
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::get_associated_token_address;
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::pubkey::Pubkey;
+use solana_program::{system_program, sysvar};
+
+use gso::GSOState;
+
+use crate::pda::*;
+
+// Builders for every GSO instruction. Accounts that are PDAs are derived
+// here, user token accounts default to the associated token accounts of the
+// authority unless the instruction takes them explicitly.
+
+fn instruction(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: gso::ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+fn gso_state_address(state: &GSOState) -> Pubkey {
+    find_gso_state_address(state.period_num, &state.project_name).0
+}
+
+// latest_period is 0 for a new project name, or the last period configured
+// under the name before the project existed.
+pub fn init_project(
+    authority: &Pubkey,
+    project_name: &str,
+    latest_period: u64,
+    default_lockup_ratio_tokens_per_million: u64,
+    default_lot_size: u64,
+    default_strike: u64,
+) -> Instruction {
+    instruction(
+        gso::accounts::GSOInitProject {
+            authority: *authority,
+            project: find_project_address(project_name).0,
+            latest_gso_state: find_gso_state_address(latest_period, project_name).0,
+            next_gso_state: find_gso_state_address(latest_period.saturating_add(1), project_name).0,
+            system_program: system_program::ID,
+        },
+        gso::instruction::InitProject {
+            project_name: project_name.to_string(),
+            latest_period,
+            default_lockup_ratio_tokens_per_million,
+            default_lot_size,
+            default_strike,
+        },
+    )
+}
+
+pub fn set_project_defaults(
+    authority: &Pubkey,
+    project_name: &str,
+    default_lockup_ratio_tokens_per_million: u64,
+    default_lot_size: u64,
+    default_strike: u64,
+) -> Instruction {
+    instruction(
+        gso::accounts::GSOSetProjectDefaults {
+            authority: *authority,
+            project: find_project_address(project_name).0,
+        },
+        gso::instruction::SetProjectDefaults {
+            default_lockup_ratio_tokens_per_million,
+            default_lot_size,
+            default_strike,
+        },
+    )
+}
+
+// Token accounts and mints that config and config_v2 need on top of the
+// derived addresses.
+pub struct ConfigAccounts {
+    pub authority: Pubkey,
+    pub so_base_account: Pubkey,
+    pub so_quote_account: Pubkey,
+    pub so_base_mint: Pubkey,
+    pub so_quote_mint: Pubkey,
+}
+
+// The so_authority_bump in args is replaced with the derived one.
+pub fn config(accounts: &ConfigAccounts, mut args: gso::instruction::Config) -> Instruction {
+    let (gso_state, _gso_state_bump) = find_gso_state_address(args.period_num, &args.project_name);
+    let (so_authority, so_authority_bump) = find_so_authority_address(&gso_state);
+    let so_name: String = so_name(&args.project_name, args.period_num);
+    let (so_state, _so_state_bump) = find_so_state_address(&so_name, &accounts.so_base_mint);
+    args.so_authority_bump = so_authority_bump;
+    instruction(
+        gso::accounts::GSOConfig {
+            authority: accounts.authority,
+            gso_state,
+            project: find_project_address(&args.project_name).0,
+            so_authority,
+            so_state,
+            so_base_vault: find_so_base_vault_address(&so_name, &accounts.so_base_mint).0,
+            so_base_account: accounts.so_base_account,
+            so_quote_account: accounts.so_quote_account,
+            so_base_mint: accounts.so_base_mint,
+            so_quote_mint: accounts.so_quote_mint,
+            so_option_mint: find_so_option_mint_address(&so_state, args.strike_price).0,
+            staking_options_program: staking_options::ID,
+            x_base_mint: find_x_base_mint_address(&gso_state, 0).0,
+            base_vault: find_base_vault_address(&gso_state, 0).0,
+            token_program: anchor_spl::token::ID,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        },
+        args,
+    )
+}
+
+// Same as config, with the lockup mint separate from the SO base mint. The
+// so_authority_bump in args is replaced with the derived one.
+pub fn config_v2(
+    accounts: &ConfigAccounts,
+    lockup_mint: &Pubkey,
+    mut args: gso::instruction::ConfigV2,
+) -> Instruction {
+    let (gso_state, _gso_state_bump) = find_gso_state_address(args.period_num, &args.project_name);
+    let (so_authority, so_authority_bump) = find_so_authority_address(&gso_state);
+    let so_name: String = so_name(&args.project_name, args.period_num);
+    let (so_state, _so_state_bump) = find_so_state_address(&so_name, &accounts.so_base_mint);
+    args.so_authority_bump = so_authority_bump;
+    instruction(
+        gso::accounts::GSOConfigV2 {
+            authority: accounts.authority,
+            gso_state,
+            project: find_project_address(&args.project_name).0,
+            so_authority,
+            so_state,
+            so_base_vault: find_so_base_vault_address(&so_name, &accounts.so_base_mint).0,
+            so_base_account: accounts.so_base_account,
+            so_quote_account: accounts.so_quote_account,
+            so_base_mint: accounts.so_base_mint,
+            so_quote_mint: accounts.so_quote_mint,
+            so_option_mint: find_so_option_mint_address(&so_state, args.strike_price).0,
+            staking_options_program: staking_options::ID,
+            x_base_mint: find_x_base_mint_address(&gso_state, 0).0,
+            lockup_mint: *lockup_mint,
+            base_vault: find_base_vault_address(&gso_state, 0).0,
+            token_program: anchor_spl::token::ID,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        },
+        args,
+    )
+}
+
+// The option and xToken accounts are the associated token accounts of the
+// authority and must already exist.
+pub fn stake(
+    state: &GSOState,
+    authority: &Pubkey,
+    user_base_account: &Pubkey,
+    amount: u64,
+    tier: u8,
+    strike_index: u8,
+) -> Instruction {
+    let gso_state: Pubkey = gso_state_address(state);
+    let so_option_mint: Pubkey =
+        find_so_option_mint_address(&state.staking_options_state, state.strike_at(strike_index)).0;
+    let x_base_mint: Pubkey = find_x_base_mint_address(&gso_state, tier).0;
+    instruction(
+        gso::accounts::GSOStake {
+            authority: *authority,
+            gso_state,
+            deposit_record: find_deposit_record_address(&gso_state, authority).0,
+            so_authority: find_so_authority_address(&gso_state).0,
+            so_option_mint,
+            so_user_option_account: get_associated_token_address(authority, &so_option_mint),
+            so_state: state.staking_options_state,
+            staking_options_program: staking_options::ID,
+            x_base_mint,
+            user_base_account: *user_base_account,
+            user_x_base_account: get_associated_token_address(authority, &x_base_mint),
+            base_vault: find_base_vault_address(&gso_state, tier).0,
+            token_program: anchor_spl::token::ID,
+            system_program: system_program::ID,
+        },
+        gso::instruction::Stake {
+            amount,
+            tier,
+            strike_index,
+        },
+    )
+}
+
+// Adds the early unstake penalty destination when the GSO has one.
+pub fn unstake(
+    state: &GSOState,
+    authority: &Pubkey,
+    user_base_account: &Pubkey,
+    amount: u64,
+    tier: u8,
+) -> Instruction {
+    let gso_state: Pubkey = gso_state_address(state);
+    let x_base_mint: Pubkey = find_x_base_mint_address(&gso_state, tier).0;
+    let mut unstake_instruction: Instruction = instruction(
+        gso::accounts::GSOUnstake {
+            authority: *authority,
+            gso_state,
+            x_base_mint,
+            user_base_account: *user_base_account,
+            user_x_base_account: get_associated_token_address(authority, &x_base_mint),
+            base_vault: find_base_vault_address(&gso_state, tier).0,
+            token_program: anchor_spl::token::ID,
+        },
+        gso::instruction::Unstake { amount, tier },
+    );
+    if state.early_unstake_penalty_destination != Pubkey::default() {
+        unstake_instruction.accounts.push(AccountMeta::new(
+            state.early_unstake_penalty_destination,
+            false,
+        ));
+    }
+    unstake_instruction
+}
+
+pub fn early_unstake(
+    state: &GSOState,
+    authority: &Pubkey,
+    user_base_account: &Pubkey,
+    amount: u64,
+    tier: u8,
+) -> Instruction {
+    let gso_state: Pubkey = gso_state_address(state);
+    // The program only accepts options of the strike with the highest ratio.
+    let strike_index: u8 = state
+        .highest_ratio_strike_index(state.subscription_period_start, tier)
+        .unwrap();
+    let so_option_mint: Pubkey =
+        find_so_option_mint_address(&state.staking_options_state, state.strike_at(strike_index)).0;
+    let x_base_mint: Pubkey = find_x_base_mint_address(&gso_state, tier).0;
+    instruction(
+        gso::accounts::GSOEarlyUnstake {
+            authority: *authority,
+            gso_state,
+            so_state: state.staking_options_state,
+            so_option_mint,
+            so_user_option_account: get_associated_token_address(authority, &so_option_mint),
+            x_base_mint,
+            user_base_account: *user_base_account,
+            user_x_base_account: get_associated_token_address(authority, &x_base_mint),
+            base_vault: find_base_vault_address(&gso_state, tier).0,
+            token_program: anchor_spl::token::ID,
+        },
+        gso::instruction::EarlyUnstake { amount, tier },
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn rollover(
+    old_state: &GSOState,
+    state: &GSOState,
+    authority: &Pubkey,
+    user_base_account: &Pubkey,
+    amount: u64,
+    old_tier: u8,
+    tier: u8,
+    strike_index: u8,
+) -> Instruction {
+    let old_gso_state: Pubkey = gso_state_address(old_state);
+    let old_x_base_mint: Pubkey = find_x_base_mint_address(&old_gso_state, old_tier).0;
+    let gso_state: Pubkey = gso_state_address(state);
+    let so_option_mint: Pubkey =
+        find_so_option_mint_address(&state.staking_options_state, state.strike_at(strike_index)).0;
+    let x_base_mint: Pubkey = find_x_base_mint_address(&gso_state, tier).0;
+    instruction(
+        gso::accounts::GSORollover {
+            authority: *authority,
+            old_gso_state,
+            old_x_base_mint,
+            old_user_x_base_account: get_associated_token_address(authority, &old_x_base_mint),
+            old_base_vault: find_base_vault_address(&old_gso_state, old_tier).0,
+            user_base_account: *user_base_account,
+            gso_state,
+            deposit_record: find_deposit_record_address(&gso_state, authority).0,
+            so_authority: find_so_authority_address(&gso_state).0,
+            so_option_mint,
+            so_user_option_account: get_associated_token_address(authority, &so_option_mint),
+            so_state: state.staking_options_state,
+            staking_options_program: staking_options::ID,
+            x_base_mint,
+            user_x_base_account: get_associated_token_address(authority, &x_base_mint),
+            base_vault: find_base_vault_address(&gso_state, tier).0,
+            token_program: anchor_spl::token::ID,
+            system_program: system_program::ID,
+        },
+        gso::instruction::Rollover {
+            amount,
+            old_tier,
+            tier,
+            strike_index,
+        },
+    )
+}
+
+pub fn withdraw(state: &GSOState, authority: &Pubkey, user_base_account: &Pubkey) -> Instruction {
+    let gso_state: Pubkey = gso_state_address(state);
+    instruction(
+        gso::accounts::GSOWithdraw {
+            authority: *authority,
+            gso_state,
+            so_authority: find_so_authority_address(&gso_state).0,
+            user_base_account: *user_base_account,
+            so_base_vault: find_so_base_vault_address(
+                &state.staking_options_name(),
+                &state.option_base_mint,
+            )
+            .0,
+            so_state: state.staking_options_state,
+            staking_options_program: staking_options::ID,
+            token_program: anchor_spl::token::ID,
+            system_program: system_program::ID,
+        },
+        gso::instruction::Withdraw {},
+    )
+}
+
+// Names the options for every strike and the xTokens of every tier.
+pub fn name_tokens(state: &GSOState, authority: &Pubkey) -> Instruction {
+    let gso_state: Pubkey = gso_state_address(state);
+    let x_base_mint: Pubkey = find_x_base_mint_address(&gso_state, 0).0;
+    let so_option_mint: Pubkey =
+        find_so_option_mint_address(&state.staking_options_state, state.strike).0;
+    let mut name_tokens_instruction: Instruction = instruction(
+        gso::accounts::GSONameTokens {
+            authority: *authority,
+            so_state: state.staking_options_state,
+            gso_state,
+            x_base_mint,
+            x_base_metadata: find_metadata_address(&x_base_mint).0,
+            token_metadata_program: mpl_token_metadata::ID,
+            so_authority: find_so_authority_address(&gso_state).0,
+            so_option_mint,
+            option_metadata: find_metadata_address(&so_option_mint).0,
+            staking_options_program: staking_options::ID,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        },
+        gso::instruction::NameTokens {},
+    );
+    for strike_level in state.strikes.iter() {
+        let option_mint: Pubkey =
+            find_so_option_mint_address(&state.staking_options_state, strike_level.strike).0;
+        name_tokens_instruction
+            .accounts
+            .push(AccountMeta::new(option_mint, false));
+        name_tokens_instruction.accounts.push(AccountMeta::new(
+            find_metadata_address(&option_mint).0,
+            false,
+        ));
+    }
+    for tier in 1..=state.tiers.len() {
+        let tier_x_base_mint: Pubkey = find_x_base_mint_address(&gso_state, tier as u8).0;
+        name_tokens_instruction
+            .accounts
+            .push(AccountMeta::new(tier_x_base_mint, false));
+        name_tokens_instruction.accounts.push(AccountMeta::new(
+            find_metadata_address(&tier_x_base_mint).0,
+            false,
+        ));
+    }
+    name_tokens_instruction
+}
+
+// Passes the base vaults of the additional tiers to be closed as well.
+pub fn close(state: &GSOState, authority: &Pubkey) -> Instruction {
+    let gso_state: Pubkey = gso_state_address(state);
+    let mut close_instruction: Instruction = instruction(
+        gso::accounts::GSOClose {
+            authority: *authority,
+            gso_state,
+            x_base_mint: find_x_base_mint_address(&gso_state, 0).0,
+            base_vault: find_base_vault_address(&gso_state, 0).0,
+            so_state: state.staking_options_state,
+            so_base_vault: find_so_base_vault_address(
+                &state.staking_options_name(),
+                &state.option_base_mint,
+            )
+            .0,
+            token_program: anchor_spl::token::ID,
+        },
+        gso::instruction::Close {},
+    );
+    for tier in 1..=state.tiers.len() {
+        close_instruction.accounts.push(AccountMeta::new(
+            find_base_vault_address(&gso_state, tier as u8).0,
+            false,
+        ));
+    }
+    close_instruction
+}
+
+pub fn propose_authority(
+    state: &GSOState,
+    authority: &Pubkey,
+    pending_authority: &Pubkey,
+) -> Instruction {
+    instruction(
+        gso::accounts::GSOProposeAuthority {
+            authority: *authority,
+            gso_state: gso_state_address(state),
+        },
+        gso::instruction::ProposeAuthority {
+            pending_authority: *pending_authority,
+        },
+    )
+}
+
+pub fn accept_authority(state: &GSOState, pending_authority: &Pubkey) -> Instruction {
+    instruction(
+        gso::accounts::GSOAcceptAuthority {
+            pending_authority: *pending_authority,
+            gso_state: gso_state_address(state),
+        },
+        gso::instruction::AcceptAuthority {},
+    )
+}
+
+pub fn propose_project_authority(
+    project_name: &str,
+    authority: &Pubkey,
+    pending_authority: &Pubkey,
+) -> Instruction {
+    instruction(
+        gso::accounts::GSOProposeProjectAuthority {
+            authority: *authority,
+            project: find_project_address(project_name).0,
+        },
+        gso::instruction::ProposeProjectAuthority {
+            pending_authority: *pending_authority,
+        },
+    )
+}
+
+pub fn accept_project_authority(project_name: &str, pending_authority: &Pubkey) -> Instruction {
+    instruction(
+        gso::accounts::GSOAcceptProjectAuthority {
+            pending_authority: *pending_authority,
+            project: find_project_address(project_name).0,
+        },
+        gso::instruction::AcceptProjectAuthority {},
+    )
+}
+
+pub fn add_tier(
+    state: &GSOState,
+    authority: &Pubkey,
+    lockup_mint: &Pubkey,
+    tier: u8,
+    lockup_period_end: u64,
+    lockup_ratio_tokens_per_million: u64,
+) -> Instruction {
+    let gso_state: Pubkey = gso_state_address(state);
+    instruction(
+        gso::accounts::GSOAddTier {
+            authority: *authority,
+            gso_state,
+            tier_zero_base_vault: find_base_vault_address(&gso_state, 0).0,
+            lockup_mint: *lockup_mint,
+            x_base_mint: find_x_base_mint_address(&gso_state, tier).0,
+            base_vault: find_base_vault_address(&gso_state, tier).0,
+            token_program: anchor_spl::token::ID,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        },
+        gso::instruction::AddTier {
+            tier,
+            lockup_period_end,
+            lockup_ratio_tokens_per_million,
+        },
+    )
+}
+
+pub fn add_strike(
+    state: &GSOState,
+    authority: &Pubkey,
+    strike: u64,
+    lockup_ratio_tokens_per_million: u64,
+) -> Instruction {
+    let gso_state: Pubkey = gso_state_address(state);
+    instruction(
+        gso::accounts::GSOAddStrike {
+            authority: *authority,
+            gso_state,
+            so_authority: find_so_authority_address(&gso_state).0,
+            so_state: state.staking_options_state,
+            so_option_mint: find_so_option_mint_address(&state.staking_options_state, strike).0,
+            staking_options_program: staking_options::ID,
+            token_program: anchor_spl::token::ID,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        },
+        gso::instruction::AddStrike {
+            strike,
+            lockup_ratio_tokens_per_million,
+        },
+    )
+}
+
+pub fn propose_slash(state: &GSOState, authority: &Pubkey, tier: u8, amount: u64) -> Instruction {
+    let gso_state: Pubkey = gso_state_address(state);
+    instruction(
+        gso::accounts::GSOProposeSlash {
+            authority: *authority,
+            gso_state,
+            base_vault: find_base_vault_address(&gso_state, tier).0,
+        },
+        gso::instruction::ProposeSlash { tier, amount },
+    )
+}
+
+pub fn execute_slash(state: &GSOState, authority: &Pubkey, tier: u8) -> Instruction {
+    let gso_state: Pubkey = gso_state_address(state);
+    instruction(
+        gso::accounts::GSOExecuteSlash {
+            authority: *authority,
+            gso_state,
+            base_vault: find_base_vault_address(&gso_state, tier).0,
+            slash_destination: state.slash_destination,
+            token_program: anchor_spl::token::ID,
+        },
+        gso::instruction::ExecuteSlash { tier },
+    )
+}
+
+pub fn borrow(
+    state: &GSOState,
+    authority: &Pubkey,
+    destination: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    let gso_state: Pubkey = gso_state_address(state);
+    instruction(
+        gso::accounts::GSOBorrow {
+            authority: *authority,
+            gso_state,
+            base_vault: find_base_vault_address(&gso_state, 0).0,
+            destination: *destination,
+            token_program: anchor_spl::token::ID,
+        },
+        gso::instruction::Borrow { amount },
+    )
+}
+
+pub fn repay(state: &GSOState, authority: &Pubkey, source: &Pubkey, amount: u64) -> Instruction {
+    let gso_state: Pubkey = gso_state_address(state);
+    instruction(
+        gso::accounts::GSORepay {
+            authority: *authority,
+            gso_state,
+            base_vault: find_base_vault_address(&gso_state, 0).0,
+            source: *source,
+            token_program: anchor_spl::token::ID,
+        },
+        gso::instruction::Repay { amount },
+    )
+}
+
+pub fn mark_default(state: &GSOState) -> Instruction {
+    instruction(
+        gso::accounts::GSOMarkDefault {
+            gso_state: gso_state_address(state),
+        },
+        gso::instruction::MarkDefault {},
+    )
+}
+
+pub fn migrate_lockup_mint(state: &GSOState) -> Instruction {
+    let gso_state: Pubkey = gso_state_address(state);
+    instruction(
+        gso::accounts::GSOMigrateLockupMint {
+            gso_state,
+            base_vault: find_base_vault_address(&gso_state, 0).0,
+        },
+        gso::instruction::MigrateLockupMint {},
+    )
+}
+
+pub fn set_pause(state: &GSOState, authority: &Pubkey, paused: u8) -> Instruction {
+    instruction(
+        gso::accounts::GSOSetPause {
+            authority: *authority,
+            gso_state: gso_state_address(state),
+        },
+        gso::instruction::SetPause { paused },
+    )
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use anchor_lang::AnchorDeserialize;
+    use gso::{LockupTier, StrikeLevel};
+
+    // Also used by the decoding tests in state.rs.
+    pub(crate) fn gso_state() -> GSOState {
+        GSOState {
+            period_num: 1,
+            subscription_period_end: 100,
+            lockup_ratio_tokens_per_million: 200_000,
+            gso_state_bump: 255,
+            so_authority_bump: 255,
+            x_base_mint_bump: 255,
+            base_vault_bump: 255,
+            strike: 50_000,
+            project_name: "TEST_PROJECT".to_string(),
+            staking_options_state: Pubkey::new_unique(),
+            authority: Pubkey::new_unique(),
+            option_base_mint: Pubkey::new_unique(),
+            lockup_period_end: 100,
+            pending_authority: Pubkey::default(),
+            paused: 0,
+            max_total_locked: 0,
+            max_per_wallet: 0,
+            total_locked: 0,
+            subscription_period_start: 0,
+            lockup_ratio_schedule: 0,
+            lockup_ratio_end_tokens_per_million: 0,
+            lockup_ratio_steps: 0,
+            tiers: vec![LockupTier {
+                lockup_period_end: 200,
+                lockup_ratio_tokens_per_million: 400_000,
+                x_base_mint_bump: 255,
+                base_vault_bump: 255,
+                total_slashed: 0,
+                total_unstaked: 0,
+            }],
+            strikes: vec![StrikeLevel {
+                strike: 60_000,
+                lockup_ratio_tokens_per_million: 100_000,
+            }],
+            max_slash_bps: 0,
+            slash_timelock: 0,
+            slash_destination: Pubkey::default(),
+            pending_slash_amount: 0,
+            pending_slash_tier: 0,
+            pending_slash_executable_ts: 0,
+            total_slashed: 0,
+            credit_limit: 0,
+            outstanding_debt: 0,
+            defaulted: false,
+            vesting_end: 0,
+            total_unstaked: 0,
+            early_unstake_penalty_bps: 0,
+            early_unstake_penalty_decay: false,
+            early_unstake_penalty_destination: Pubkey::default(),
+            lockup_mint: Pubkey::new_unique(),
+            lockup_decimals: 6,
+            option_base_decimals: 6,
+            lockup_ratio_num: 0,
+            lockup_ratio_den: 0,
+            so_name: "GSOTEST_PROJECT-1".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_stake_accounts() {
+        let state: GSOState = gso_state();
+        let authority: Pubkey = Pubkey::new_unique();
+        let user_base_account: Pubkey = Pubkey::new_unique();
+        let stake_instruction: Instruction =
+            stake(&state, &authority, &user_base_account, 1_000, 1, 1);
+
+        let gso_state: Pubkey = find_gso_state_address(1, "TEST_PROJECT").0;
+        let x_base_mint: Pubkey = find_x_base_mint_address(&gso_state, 1).0;
+        let so_option_mint: Pubkey =
+            find_so_option_mint_address(&state.staking_options_state, 60_000).0;
+        let keys: Vec<Pubkey> = stake_instruction
+            .accounts
+            .iter()
+            .map(|account| account.pubkey)
+            .collect();
+        assert_eq!(stake_instruction.program_id, gso::ID);
+        assert_eq!(
+            keys,
+            vec![
+                authority,
+                gso_state,
+                find_deposit_record_address(&gso_state, &authority).0,
+                find_so_authority_address(&gso_state).0,
+                so_option_mint,
+                get_associated_token_address(&authority, &so_option_mint),
+                state.staking_options_state,
+                staking_options::ID,
+                x_base_mint,
+                user_base_account,
+                get_associated_token_address(&authority, &x_base_mint),
+                find_base_vault_address(&gso_state, 1).0,
+                anchor_spl::token::ID,
+                system_program::ID,
+            ]
+        );
+        assert!(stake_instruction.accounts[0].is_signer);
+    }
+
+    #[test]
+    fn test_stake_data() {
+        let state: GSOState = gso_state();
+        let stake_instruction: Instruction = stake(
+            &state,
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            1_000,
+            1,
+            1,
+        );
+        // 8 bytes of sighash then the borsh encoded args.
+        let args = gso::instruction::Stake::try_from_slice(&stake_instruction.data[8..]).unwrap();
+        assert_eq!(args.amount, 1_000);
+        assert_eq!(args.tier, 1);
+        assert_eq!(args.strike_index, 1);
+    }
+
+    #[test]
+    fn test_unstake_penalty_destination() {
+        let mut state: GSOState = gso_state();
+        let authority: Pubkey = Pubkey::new_unique();
+        let user_base_account: Pubkey = Pubkey::new_unique();
+        assert_eq!(
+            unstake(&state, &authority, &user_base_account, 1_000, 0)
+                .accounts
+                .len(),
+            7
+        );
+
+        state.early_unstake_penalty_destination = Pubkey::new_unique();
+        let unstake_instruction: Instruction =
+            unstake(&state, &authority, &user_base_account, 1_000, 0);
+        assert_eq!(unstake_instruction.accounts.len(), 8);
+        assert_eq!(
+            unstake_instruction.accounts[7].pubkey,
+            state.early_unstake_penalty_destination
+        );
+        assert!(unstake_instruction.accounts[7].is_writable);
+    }
+
+    #[test]
+    fn test_early_unstake_highest_ratio_strike() {
+        let mut state: GSOState = gso_state();
+        let authority: Pubkey = Pubkey::new_unique();
+        let user_base_account: Pubkey = Pubkey::new_unique();
+        let early_unstake_instruction: Instruction =
+            early_unstake(&state, &authority, &user_base_account, 1_000, 0);
+        assert_eq!(
+            early_unstake_instruction.accounts[3].pubkey,
+            find_so_option_mint_address(&state.staking_options_state, 50_000).0
+        );
+
+        // A strike that earns more per token is the one exits are priced at.
+        state.strikes[0].lockup_ratio_tokens_per_million = 300_000;
+        let early_unstake_instruction: Instruction =
+            early_unstake(&state, &authority, &user_base_account, 1_000, 0);
+        assert_eq!(
+            early_unstake_instruction.accounts[3].pubkey,
+            find_so_option_mint_address(&state.staking_options_state, 60_000).0
+        );
+    }
+
+    #[test]
+    fn test_close_tier_vaults() {
+        let state: GSOState = gso_state();
+        let close_instruction: Instruction = close(&state, &state.authority);
+        let gso_state: Pubkey = find_gso_state_address(1, "TEST_PROJECT").0;
+        assert_eq!(close_instruction.accounts.len(), 8);
+        assert_eq!(
+            close_instruction.accounts[7].pubkey,
+            find_base_vault_address(&gso_state, 1).0
+        );
+    }
+
+    #[test]
+    fn test_name_tokens_extra_strikes_and_tiers() {
+        let state: GSOState = gso_state();
+        let name_tokens_instruction: Instruction = name_tokens(&state, &state.authority);
+        let option_mint: Pubkey =
+            find_so_option_mint_address(&state.staking_options_state, 60_000).0;
+        let tier_x_base_mint: Pubkey =
+            find_x_base_mint_address(&find_gso_state_address(1, "TEST_PROJECT").0, 1).0;
+        let count: usize = name_tokens_instruction.accounts.len();
+        assert_eq!(
+            name_tokens_instruction.accounts[count - 4].pubkey,
+            option_mint
+        );
+        assert_eq!(
+            name_tokens_instruction.accounts[count - 3].pubkey,
+            find_metadata_address(&option_mint).0
+        );
+        assert_eq!(
+            name_tokens_instruction.accounts[count - 2].pubkey,
+            tier_x_base_mint
+        );
+        assert_eq!(
+            name_tokens_instruction.accounts[count - 1].pubkey,
+            find_metadata_address(&tier_x_base_mint).0
+        );
+    }
+}
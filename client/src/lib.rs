@@ -0,0 +1,10 @@
+// Client helpers for GSO. PDA derivation, instruction builders and decoding of
+// the program accounts, so integrators do not need to copy the seeds or the
+// account lists out of the program.
+
+pub mod instruction;
+mod pda;
+mod state;
+
+pub use crate::pda::*;
+pub use crate::state::*;
@@ -0,0 +1,243 @@
+use solana_program::pubkey::Pubkey;
+
+use gso::{
+    tier_seed, BASE_VAULT_SEED, DEPOSIT_RECORD_SEED, GSO_STATE_SEED, PROJECT_SEED,
+    SO_AUTHORITY_SEED, X_GSO_SEED,
+};
+use staking_options::{SO_CONFIG_SEED, SO_MINT_SEED, SO_VAULT_SEED};
+
+// Addresses for the PDAs of GSO and the SO state under it. Each helper uses
+// the same seeds as the `seeds =` constraint for that account in the program.
+
+pub fn find_project_address(project_name: &str) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[PROJECT_SEED, project_name.as_bytes()], &gso::ID)
+}
+
+pub fn find_gso_state_address(period_num: u64, project_name: &str) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            GSO_STATE_SEED,
+            &period_num.to_be_bytes(),
+            project_name.as_bytes(),
+        ],
+        &gso::ID,
+    )
+}
+
+pub fn find_x_base_mint_address(gso_state: &Pubkey, tier: u8) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[X_GSO_SEED, &gso_state.to_bytes(), tier_seed(&tier)],
+        &gso::ID,
+    )
+}
+
+pub fn find_base_vault_address(gso_state: &Pubkey, tier: u8) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[BASE_VAULT_SEED, &gso_state.to_bytes(), tier_seed(&tier)],
+        &gso::ID,
+    )
+}
+
+pub fn find_so_authority_address(gso_state: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[SO_AUTHORITY_SEED, &gso_state.to_bytes()], &gso::ID)
+}
+
+pub fn find_deposit_record_address(gso_state: &Pubkey, owner: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            DEPOSIT_RECORD_SEED,
+            &gso_state.to_bytes(),
+            &owner.to_bytes(),
+        ],
+        &gso::ID,
+    )
+}
+
+// Name of the SO state that config creates for a period of a project.
+pub fn so_name(project_name: &str, period_num: u64) -> String {
+    gso::so_name(project_name, period_num)
+}
+
+// so_name is the one from config, or GSOState::staking_options_name for a
+// configured GSO, which also covers states from before the period was in it.
+pub fn find_so_state_address(so_name: &str, so_base_mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[SO_CONFIG_SEED, so_name.as_bytes(), &so_base_mint.to_bytes()],
+        &staking_options::ID,
+    )
+}
+
+pub fn find_so_base_vault_address(so_name: &str, so_base_mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[SO_VAULT_SEED, so_name.as_bytes(), &so_base_mint.to_bytes()],
+        &staking_options::ID,
+    )
+}
+
+pub fn find_so_option_mint_address(so_state: &Pubkey, strike: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[SO_MINT_SEED, &so_state.to_bytes(), &strike.to_be_bytes()],
+        &staking_options::ID,
+    )
+}
+
+pub fn find_metadata_address(mint: &Pubkey) -> (Pubkey, u8) {
+    mpl_token_metadata::pda::find_metadata_account(mint)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn project_name() -> String {
+        "TEST_PROJECT".to_string()
+    }
+
+    #[test]
+    fn test_gso_state_address() {
+        // seeds = [GSO_STATE_SEED, &period_num.to_be_bytes(), project_name.as_bytes()]
+        let (address, bump) = find_gso_state_address(1, &project_name());
+        let expected = Pubkey::create_program_address(
+            &[
+                b"GSO-state",
+                &1u64.to_be_bytes(),
+                project_name().as_bytes(),
+                &[bump],
+            ],
+            &gso::ID,
+        )
+        .unwrap();
+        assert_eq!(address, expected);
+        assert_ne!(address, find_gso_state_address(2, &project_name()).0);
+    }
+
+    #[test]
+    fn test_tier_zero_addresses() {
+        // Tier 0 has no tier seed so it matches GSOs from before tiers.
+        let (gso_state, _) = find_gso_state_address(1, &project_name());
+        let (x_base_mint, x_base_mint_bump) = find_x_base_mint_address(&gso_state, 0);
+        assert_eq!(
+            x_base_mint,
+            Pubkey::create_program_address(
+                &[b"xGSO", &gso_state.to_bytes(), &[x_base_mint_bump]],
+                &gso::ID
+            )
+            .unwrap()
+        );
+        let (base_vault, base_vault_bump) = find_base_vault_address(&gso_state, 0);
+        assert_eq!(
+            base_vault,
+            Pubkey::create_program_address(
+                &[b"base-vault", &gso_state.to_bytes(), &[base_vault_bump]],
+                &gso::ID
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_tier_addresses() {
+        let (gso_state, _) = find_gso_state_address(1, &project_name());
+        let (x_base_mint, x_base_mint_bump) = find_x_base_mint_address(&gso_state, 2);
+        assert_eq!(
+            x_base_mint,
+            Pubkey::create_program_address(
+                &[b"xGSO", &gso_state.to_bytes(), &[2], &[x_base_mint_bump]],
+                &gso::ID
+            )
+            .unwrap()
+        );
+        let (base_vault, base_vault_bump) = find_base_vault_address(&gso_state, 2);
+        assert_eq!(
+            base_vault,
+            Pubkey::create_program_address(
+                &[
+                    b"base-vault",
+                    &gso_state.to_bytes(),
+                    &[2],
+                    &[base_vault_bump]
+                ],
+                &gso::ID
+            )
+            .unwrap()
+        );
+        assert_ne!(base_vault, find_base_vault_address(&gso_state, 0).0);
+    }
+
+    #[test]
+    fn test_so_authority_and_deposit_record_addresses() {
+        let (gso_state, _) = find_gso_state_address(1, &project_name());
+        let (so_authority, so_authority_bump) = find_so_authority_address(&gso_state);
+        assert_eq!(
+            so_authority,
+            Pubkey::create_program_address(
+                &[b"gso", &gso_state.to_bytes(), &[so_authority_bump]],
+                &gso::ID
+            )
+            .unwrap()
+        );
+        let owner = Pubkey::new_unique();
+        let (deposit_record, deposit_record_bump) = find_deposit_record_address(&gso_state, &owner);
+        assert_eq!(
+            deposit_record,
+            Pubkey::create_program_address(
+                &[
+                    b"deposit-record",
+                    &gso_state.to_bytes(),
+                    &owner.to_bytes(),
+                    &[deposit_record_bump]
+                ],
+                &gso::ID
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_project_address() {
+        let (project, bump) = find_project_address(&project_name());
+        assert_eq!(
+            project,
+            Pubkey::create_program_address(
+                &[b"project", project_name().as_bytes(), &[bump]],
+                &gso::ID
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_so_addresses() {
+        let so_base_mint = Pubkey::new_unique();
+        assert_eq!(so_name(&project_name(), 1), "GSOTEST_PROJECT-1");
+        let (so_state, so_state_bump) =
+            find_so_state_address(&so_name(&project_name(), 1), &so_base_mint);
+        assert_eq!(
+            so_state,
+            Pubkey::create_program_address(
+                &[
+                    b"so-config",
+                    b"GSOTEST_PROJECT-1",
+                    &so_base_mint.to_bytes(),
+                    &[so_state_bump]
+                ],
+                &staking_options::ID
+            )
+            .unwrap()
+        );
+        let (option_mint, option_mint_bump) = find_so_option_mint_address(&so_state, 50_000);
+        assert_eq!(
+            option_mint,
+            Pubkey::create_program_address(
+                &[
+                    b"so-mint",
+                    &so_state.to_bytes(),
+                    &50_000u64.to_be_bytes(),
+                    &[option_mint_bump]
+                ],
+                &staking_options::ID
+            )
+            .unwrap()
+        );
+    }
+}
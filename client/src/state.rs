@@ -0,0 +1,137 @@
+use anchor_lang::{AccountDeserialize, Result};
+use solana_program::pubkey::Pubkey;
+
+use gso::{GSOState, Project};
+
+// Anything that can load the raw data of an account, for example a wrapper
+// around an RpcClient or a BanksClient. Kept as a trait so that this crate
+// does not pick the RPC client version for the caller.
+pub trait AccountFetcher {
+    type Error: From<anchor_lang::error::Error>;
+
+    fn get_account_data(&self, address: &Pubkey) -> std::result::Result<Vec<u8>, Self::Error>;
+}
+
+// Decodes a GSOState from account data. Checks the anchor discriminator.
+pub fn decode_gso_state(data: &[u8]) -> Result<GSOState> {
+    let mut data: &[u8] = data;
+    GSOState::try_deserialize(&mut data)
+}
+
+pub fn decode_project(data: &[u8]) -> Result<Project> {
+    let mut data: &[u8] = data;
+    Project::try_deserialize(&mut data)
+}
+
+pub fn fetch_gso_state<F: AccountFetcher>(
+    fetcher: &F,
+    address: &Pubkey,
+) -> std::result::Result<GSOState, F::Error> {
+    let data: Vec<u8> = fetcher.get_account_data(address)?;
+    Ok(decode_gso_state(&data)?)
+}
+
+pub fn fetch_project<F: AccountFetcher>(
+    fetcher: &F,
+    address: &Pubkey,
+) -> std::result::Result<Project, F::Error> {
+    let data: Vec<u8> = fetcher.get_account_data(address)?;
+    Ok(decode_project(&data)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::tests::gso_state;
+    use anchor_lang::AccountSerialize;
+
+    // GSOState accounts are allocated with this much space, so fields added
+    // since an account was created read as zeros.
+    const GSO_STATE_SPACE: usize = 1_000;
+
+    fn project() -> Project {
+        Project {
+            authority: Pubkey::new_unique(),
+            project_name: "TEST_PROJECT".to_string(),
+            latest_period: 3,
+            default_lockup_ratio_tokens_per_million: 200_000,
+            default_lot_size: 1,
+            default_strike: 50_000,
+            bump: 255,
+            pending_authority: Pubkey::default(),
+        }
+    }
+
+    struct Accounts(Vec<u8>);
+
+    impl AccountFetcher for Accounts {
+        type Error = anchor_lang::error::Error;
+
+        fn get_account_data(&self, _address: &Pubkey) -> Result<Vec<u8>> {
+            Ok(self.0.clone())
+        }
+    }
+
+    #[test]
+    fn test_fetch_project() {
+        let project: Project = project();
+        let mut data: Vec<u8> = Vec::new();
+        project.try_serialize(&mut data).unwrap();
+
+        let fetched: Project = fetch_project(&Accounts(data), &Pubkey::new_unique()).unwrap();
+        assert_eq!(fetched.authority, project.authority);
+        assert_eq!(fetched.project_name, "TEST_PROJECT");
+        assert_eq!(fetched.latest_period, 3);
+    }
+
+    #[test]
+    fn test_decode_gso_state() {
+        let state: GSOState = gso_state();
+        let mut data: Vec<u8> = Vec::new();
+        state.try_serialize(&mut data).unwrap();
+        assert!(data.len() <= GSO_STATE_SPACE);
+        data.resize(GSO_STATE_SPACE, 0);
+
+        let decoded: GSOState = decode_gso_state(&data).unwrap();
+        let mut reserialized: Vec<u8> = Vec::new();
+        decoded.try_serialize(&mut reserialized).unwrap();
+        assert_eq!(reserialized, data[..reserialized.len()]);
+        assert_eq!(decoded.option_base_mint, state.option_base_mint);
+        assert_eq!(decoded.lockup_mint, state.lockup_mint);
+        assert_eq!(decoded.tiers.len(), 1);
+        assert_eq!(decoded.strikes[0].strike, 60_000);
+        assert_eq!(decoded.staking_options_name(), "GSOTEST_PROJECT-1");
+    }
+
+    #[test]
+    fn test_decode_legacy_gso_state() {
+        // The original layout ends at lockup_period_end, after the name and
+        // three pubkeys.
+        let state: GSOState = gso_state();
+        let legacy_len: usize =
+            8 + 8 + 8 + 8 + 4 + 8 + 4 + state.project_name.len() + 32 + 32 + 32 + 8;
+        let mut data: Vec<u8> = Vec::new();
+        state.try_serialize(&mut data).unwrap();
+        data.truncate(legacy_len);
+        data.resize(GSO_STATE_SPACE, 0);
+
+        let decoded: GSOState = decode_gso_state(&data).unwrap();
+        assert_eq!(decoded.project_name, "TEST_PROJECT");
+        assert_eq!(decoded.option_base_mint, state.option_base_mint);
+        assert_eq!(decoded.lockup_period_end, state.lockup_period_end);
+        assert_eq!(decoded.pending_authority, Pubkey::default());
+        assert!(decoded.tiers.is_empty());
+        assert!(decoded.strikes.is_empty());
+        assert_eq!(decoded.lockup_mint, Pubkey::default());
+        assert_eq!(decoded.lockup_decimals, 0);
+        assert_eq!(decoded.lockup_ratio_den, 0);
+        assert_eq!(decoded.staking_options_name(), "GSOTEST_PROJECT");
+    }
+
+    #[test]
+    fn test_decode_wrong_account() {
+        let mut data: Vec<u8> = Vec::new();
+        project().try_serialize(&mut data).unwrap();
+        assert!(decode_gso_state(&data).is_err());
+    }
+}
@@ -1,13 +1,62 @@
 use anchor_lang::prelude::*;
+use vipers::prelude::*;
+
+use crate::errors::ErrorCode;
 
 pub const GSO_STATE_SEED: &[u8] = b"GSO-state";
 pub const X_GSO_SEED: &[u8] = b"xGSO";
 pub const BASE_VAULT_SEED: &[u8] = b"base-vault";
 pub const SO_AUTHORITY_SEED: &[u8] = b"gso";
+pub const DEPOSIT_RECORD_SEED: &[u8] = b"deposit-record";
+pub const PROJECT_SEED: &[u8] = b"project";
+
+// Bits in GSOState.paused. Each action can be paused on its own so that
+// unstaking can stay open while deposits are frozen.
+pub const PAUSE_STAKE: u8 = 1 << 0;
+pub const PAUSE_UNSTAKE: u8 = 1 << 1;
+pub const PAUSE_WITHDRAW: u8 = 1 << 2;
+
+// Values for GSOState.lockup_ratio_schedule. Flat uses
+// lockup_ratio_tokens_per_million for the whole subscription period. Linear
+// and stepwise decay from lockup_ratio_tokens_per_million at the subscription
+// start to lockup_ratio_end_tokens_per_million at the subscription end.
+pub const RATIO_SCHEDULE_FLAT: u8 = 0;
+pub const RATIO_SCHEDULE_LINEAR: u8 = 1;
+pub const RATIO_SCHEDULE_STEPWISE: u8 = 2;
+
+// Shortest slash_timelock when slashing is enabled, so that stakers have time
+// to see a proposed slash before it executes.
+pub const MIN_SLASH_TIMELOCK: u64 = 24 * 60 * 60;
+
+// Tier 0 is the original x_base_mint and base_vault. Additional lockup tiers
+// are added with add_tier.
+pub const MAX_TIERS: usize = 4;
+
+// Strike index 0 is the strike from config. Additional strikes are added with
+// add_strike.
+pub const MAX_STRIKES: usize = 8;
+
+// Extra seed for the x_base_mint and base_vault of a tier. Tier 0 uses an
+// empty seed so its addresses are the same as before tiers existed.
+pub fn tier_seed(tier: &u8) -> &[u8] {
+    if *tier == 0 {
+        &[]
+    } else {
+        std::slice::from_ref(tier)
+    }
+}
+
+// Name of the SO that config creates for a period. The period is in it so
+// that every period of a project gets its own SO state and vault, even when
+// they share the option base mint.
+pub fn so_name(project_name: &str, period_num: u64) -> String {
+    format!("GSO{}-{}", project_name, period_num)
+}
 
 #[account]
 pub struct GSOState {
-    // Number of the period. Starts at 0. Not strictly enforced.
+    // Number of the period. Starts at 1 and config enforces that each period
+    // is the next one for the Project.
     pub period_num: u64,
 
     // Matches the SO subscriptoin period end.
@@ -25,8 +74,7 @@ pub struct GSOState {
     // Strike in atoms for the quote.
     pub strike: u64,
 
-    // Name in this state gets prepended with "GSO" and is then used at the SO
-    // level.
+    // Name of the project, which the SO name is made from. See so_name.
     pub project_name: String,
 
     // Just an address, used for account validation.
@@ -35,10 +83,1219 @@ pub struct GSOState {
     // Saved so we know who can withdraw.
     pub authority: Pubkey,
 
-    // Base mint stored so it does not need to be on the.
-    pub base_mint: Pubkey,
+    // SO base mint, the mint the options are for. Also the lockup mint for
+    // config, but config_v2 can lock a different mint.
+    pub option_base_mint: Pubkey,
 
     // Time in seconds for when users can unstake.
     pub lockup_period_end: u64,
+
+    // Proposed new authority. Only takes effect once that key signs an
+    // accept_authority. Default pubkey when there is no pending transfer.
+    pub pending_authority: Pubkey,
+
+    // Bitmask of PAUSE_* flags for actions that are currently disabled.
+    pub paused: u8,
+
+    // Cap on the total tokens locked in the base vault. 0 means no cap.
+    pub max_total_locked: u64,
+
+    // Cap on the tokens any single wallet can stake. 0 means no cap.
+    pub max_per_wallet: u64,
+
+    // Tokens currently locked through stake. Decremented on unstake.
+    pub total_locked: u64,
+
+    // Time in seconds for when users can start staking. 0 means staking is
+    // open as soon as the GSO is configured.
+    pub subscription_period_start: u64,
+
+    // One of the RATIO_SCHEDULE_* values.
+    pub lockup_ratio_schedule: u8,
+
+    // Lockup ratio at the end of the subscription period when there is a
+    // schedule. lockup_ratio_tokens_per_million is the starting ratio.
+    pub lockup_ratio_end_tokens_per_million: u64,
+
+    // Number of equal length steps for the stepwise schedule.
+    pub lockup_ratio_steps: u64,
+
+    // Additional lockup tiers. Tier n is at index n - 1.
+    pub tiers: Vec<LockupTier>,
+
+    // Additional strikes. Strike index n is at index n - 1.
+    pub strikes: Vec<StrikeLevel>,
+
+    // Maximum share of a base vault in basis points that the authority can
+    // slash in total. 0 means slashing is disabled. Fixed at config so stakers
+    // can see it before depositing.
+    pub max_slash_bps: u64,
+
+    // Seconds between propose_slash and when execute_slash is allowed.
+    pub slash_timelock: u64,
+
+    // Token account that slashed tokens are sent to.
+    pub slash_destination: Pubkey,
+
+    // Slash waiting on the timelock. pending_slash_amount of 0 means there is
+    // no pending slash.
+    pub pending_slash_amount: u64,
+    pub pending_slash_tier: u8,
+    pub pending_slash_executable_ts: u64,
+
+    // Total slashed from the tier 0 base vault.
+    pub total_slashed: u64,
+
+    // Maximum the authority can borrow from the tier 0 base vault. 0 means
+    // there is no credit line.
+    pub credit_limit: u64,
+
+    // Borrowed and not yet repaid. Must be 0 by lockup_period_end.
+    pub outstanding_debt: u64,
+
+    // Set by anyone through mark_default when debt is still outstanding after
+    // lockup_period_end. Stays set as a record even if repaid later.
+    pub defaulted: bool,
+
+    // Time in seconds for when everything is unlocked. Between
+    // lockup_period_end and vesting_end tokens unlock linearly. 0 means
+    // everything unlocks at lockup_period_end.
+    pub vesting_end: u64,
+
+    // xTokens of tier 0 burned through unstake, used for the vesting curve.
+    pub total_unstaked: u64,
+
+    // Haircut in basis points on tokens unstaked before the lockup ends. 0
+    // means early unstaking is not allowed.
+    pub early_unstake_penalty_bps: u64,
+
+    // When set the penalty decays linearly from the subscription start to 0 at
+    // the lockup end.
+    pub early_unstake_penalty_decay: bool,
+
+    // Token account that penalties are sent to. Default pubkey means the
+    // penalty stays in the base vault for the remaining stakers, and early
+    // unstaking only opens once the subscription has ended.
+    pub early_unstake_penalty_destination: Pubkey,
+
+    // Mint that is staked into the base vaults. Default pubkey for states
+    // configured before this was stored until migrate_lockup_mint is run.
+    pub lockup_mint: Pubkey,
+
+    // Decimals of the lockup and option base mints. Lockup ratios are in whole
+    // tokens and these convert them to atoms. States from before these were
+    // stored have both at 0, so their ratios stay per atom.
+    pub lockup_decimals: u8,
+    pub option_base_decimals: u8,
+
+    // Exact base lockup ratio as lockup_ratio_num / lockup_ratio_den whole
+    // option tokens per whole lockup token, for ratios that per million can
+    // not express. 0 for lockup_ratio_den means lockup_ratio_tokens_per_million
+    // is the ratio, which is also what states from before these were stored
+    // get. Tier, strike and schedule ratios stay relative to
+    // lockup_ratio_tokens_per_million either way.
+    pub lockup_ratio_num: u64,
+    pub lockup_ratio_den: u64,
+
+    // Name of the SO for this period. Empty for states from before it was
+    // stored, whose SO name is "GSO" with the project name.
+    pub so_name: String,
     // Padding
 }
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct LockupTier {
+    // Time in seconds for when users of this tier can unstake.
+    pub lockup_period_end: u64,
+
+    // Lockup ratio for this tier. Any schedule on the GSO scales it the same
+    // way as the base lockup ratio.
+    pub lockup_ratio_tokens_per_million: u64,
+
+    pub x_base_mint_bump: u8,
+    pub base_vault_bump: u8,
+
+    // Total slashed from this tier's base vault.
+    pub total_slashed: u64,
+
+    // xTokens of this tier burned through unstake.
+    pub total_unstaked: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct StrikeLevel {
+    // Strike in atoms for the quote.
+    pub strike: u64,
+
+    // Lockup ratio for this strike. Scales the same way as the lockup ratio of
+    // a tier.
+    pub lockup_ratio_tokens_per_million: u64,
+}
+
+// Config params for the features added on top of the original GSO. Each one is
+// off when left at 0. The fields are in the order they used to be passed to
+// config, so the instruction data is the same.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default)]
+pub struct ConfigOptions {
+    // Caps on the total and per wallet stakes. 0 means no limit.
+    pub max_total_locked: u64,
+    pub max_per_wallet: u64,
+
+    // When staking opens. 0 opens it immediately.
+    pub subscription_period_start: u64,
+
+    // One of the RATIO_SCHEDULE_* values, so that earlier stakers earn more.
+    pub lockup_ratio_schedule: u8,
+    pub lockup_ratio_end_tokens_per_million: u64,
+    pub lockup_ratio_steps: u64,
+
+    // Slashing is enabled with a max_slash_bps above 0 and can not be changed
+    // after config.
+    pub max_slash_bps: u64,
+    pub slash_timelock: u64,
+    pub slash_destination: Pubkey,
+
+    // Lets the authority borrow from the base vault until the lockup ends.
+    pub credit_limit: u64,
+
+    // Releases tokens linearly from the lockup end instead of all at once.
+    pub vesting_end: u64,
+
+    // Allows unstaking before the lockup ends for a haircut.
+    pub early_unstake_penalty_bps: u64,
+    pub early_unstake_penalty_decay: bool,
+    pub early_unstake_penalty_destination: Pubkey,
+
+    // Replaces the per million ratio with the exact lockup_ratio_num /
+    // lockup_ratio_den options per token when lockup_ratio_den is above 0.
+    pub lockup_ratio_num: u64,
+    pub lockup_ratio_den: u64,
+}
+
+impl GSOState {
+    // Name that the SO state and vault of this GSO are seeded with.
+    pub fn staking_options_name(&self) -> String {
+        if self.so_name.is_empty() {
+            format!("GSO{}", self.project_name)
+        } else {
+            self.so_name.clone()
+        }
+    }
+
+    pub fn is_valid_tier(&self, tier: u8) -> bool {
+        (tier as usize) <= self.tiers.len()
+    }
+
+    // Time in seconds for when the tier can unstake.
+    pub fn tier_lockup_period_end(&self, tier: u8) -> u64 {
+        if tier > 0 {
+            return self.tiers[tier as usize - 1].lockup_period_end;
+        }
+        if self.lockup_period_end > 0 {
+            self.lockup_period_end
+        } else {
+            // TODO: Remove this once all GSOState has a lockup_period_end. This
+            // is for backwards compatibility only.
+            self.subscription_period_end
+        }
+    }
+
+    pub fn is_valid_strike_index(&self, strike_index: u8) -> bool {
+        (strike_index as usize) <= self.strikes.len()
+    }
+
+    pub fn strike_at(&self, strike_index: u8) -> u64 {
+        if strike_index == 0 {
+            self.strike
+        } else {
+            self.strikes[strike_index as usize - 1].strike
+        }
+    }
+
+    pub fn tier_x_base_mint_bump(&self, tier: u8) -> u8 {
+        if tier == 0 {
+            self.x_base_mint_bump
+        } else {
+            self.tiers[tier as usize - 1].x_base_mint_bump
+        }
+    }
+
+    pub fn tier_base_vault_bump(&self, tier: u8) -> u8 {
+        if tier == 0 {
+            self.base_vault_bump
+        } else {
+            self.tiers[tier as usize - 1].base_vault_bump
+        }
+    }
+
+    pub fn tier_total_slashed(&self, tier: u8) -> u64 {
+        if tier == 0 {
+            self.total_slashed
+        } else {
+            self.tiers[tier as usize - 1].total_slashed
+        }
+    }
+
+    pub fn set_tier_total_slashed(&mut self, tier: u8, total_slashed: u64) {
+        if tier == 0 {
+            self.total_slashed = total_slashed;
+        } else {
+            self.tiers[tier as usize - 1].total_slashed = total_slashed;
+        }
+    }
+
+    pub fn tier_total_unstaked(&self, tier: u8) -> u64 {
+        if tier == 0 {
+            self.total_unstaked
+        } else {
+            self.tiers[tier as usize - 1].total_unstaked
+        }
+    }
+
+    pub fn set_tier_total_unstaked(&mut self, tier: u8, total_unstaked: u64) {
+        if tier == 0 {
+            self.total_unstaked = total_unstaked;
+        } else {
+            self.tiers[tier as usize - 1].total_unstaked = total_unstaked;
+        }
+    }
+
+    // Time in seconds for when the tier is fully vested. Tiers vest over the
+    // same duration as tier 0, starting from their own lockup end. 0 if there
+    // is no vesting.
+    pub fn tier_vesting_end(&self, tier: u8) -> Result<u64> {
+        if self.vesting_end == 0 {
+            return Ok(0);
+        }
+        let vesting_duration: u64 = self
+            .vesting_end
+            .checked_sub(self.tier_lockup_period_end(0))
+            .ok_or_else(|| error!(ErrorCode::MathOverflow))?;
+        self.tier_lockup_period_end(tier)
+            .checked_add(vesting_duration)
+            .ok_or_else(|| error!(ErrorCode::MathOverflow))
+    }
+
+    // Lockup ratio for an effective per million ratio from
+    // effective_lockup_ratio. Without lockup_ratio_den the per million ratio
+    // is used as is. With it the base ratio is lockup_ratio_num /
+    // lockup_ratio_den and the schedule, tier and strike scale that by the
+    // same factor as they scale lockup_ratio_tokens_per_million.
+    pub fn lockup_ratio(&self, effective_tokens_per_million: u64) -> Result<math::Ratio> {
+        if self.lockup_ratio_den == 0 {
+            return Ok(math::Ratio::per_million(effective_tokens_per_million));
+        }
+        math::Ratio {
+            numerator: self.lockup_ratio_num as u128,
+            denominator: self.lockup_ratio_den as u128,
+        }
+        .scale(
+            effective_tokens_per_million as u128,
+            self.lockup_ratio_tokens_per_million as u128,
+        )
+    }
+
+    // Option base atoms for amount of the lockup mint at the lockup ratio.
+    pub fn options_for_lockup(
+        &self,
+        amount: u64,
+        effective_tokens_per_million: u64,
+        rounding: math::Rounding,
+    ) -> Result<u64> {
+        math::lockup_to_options(
+            amount,
+            self.lockup_ratio(effective_tokens_per_million)?,
+            self.lockup_decimals,
+            self.option_base_decimals,
+            rounding,
+        )
+    }
+
+    // Lockup mint atoms needed for options at the lockup ratio, rounded up.
+    pub fn lockup_for_options(
+        &self,
+        options: u64,
+        effective_tokens_per_million: u64,
+    ) -> Result<u64> {
+        math::options_to_lockup(
+            options,
+            self.lockup_ratio(effective_tokens_per_million)?,
+            self.lockup_decimals,
+            self.option_base_decimals,
+        )
+    }
+
+    // Lockup ratio that applies to a stake into the tier and strike at now_ts.
+    // Tier and strike ratios are relative to lockup_ratio_tokens_per_million,
+    // so with both the scheduled ratio is scaled by each of them.
+    pub fn effective_lockup_ratio(&self, now_ts: u64, tier: u8, strike_index: u8) -> Result<u64> {
+        let mut ratio: u64 = math::scheduled_ratio(
+            self.lockup_ratio_schedule,
+            self.lockup_ratio_tokens_per_million,
+            self.lockup_ratio_end_tokens_per_million,
+            self.lockup_ratio_steps,
+            self.subscription_period_start,
+            self.subscription_period_end,
+            now_ts,
+        )?;
+        if tier > 0 {
+            ratio = math::mul_div_floor(
+                ratio,
+                self.tiers[tier as usize - 1].lockup_ratio_tokens_per_million,
+                self.lockup_ratio_tokens_per_million,
+            )?;
+        }
+        if strike_index > 0 {
+            ratio = math::mul_div_floor(
+                ratio,
+                self.strikes[strike_index as usize - 1].lockup_ratio_tokens_per_million,
+                self.lockup_ratio_tokens_per_million,
+            )?;
+        }
+        Ok(ratio)
+    }
+
+    // Strike that earns the most options per token at now_ts in tier. Ties go
+    // to the lowest strike index.
+    pub fn highest_ratio_strike_index(&self, now_ts: u64, tier: u8) -> Result<u8> {
+        let mut best_strike_index: u8 = 0;
+        let mut best_ratio: u64 = self.effective_lockup_ratio(now_ts, tier, 0)?;
+        for strike_index in 1..=self.strikes.len() as u8 {
+            let ratio: u64 = self.effective_lockup_ratio(now_ts, tier, strike_index)?;
+            if ratio > best_ratio {
+                best_strike_index = strike_index;
+                best_ratio = ratio;
+            }
+        }
+        Ok(best_strike_index)
+    }
+}
+
+// Running total of what a wallet has staked into a GSO, used for enforcing
+// max_per_wallet.
+#[account]
+pub struct DepositRecord {
+    pub gso_state: Pubkey,
+    pub owner: Pubkey,
+    pub amount_deposited: u64,
+    pub bump: u8,
+}
+
+pub const DEPOSIT_RECORD_SPACE: usize = 8 + 32 + 32 + 8 + 1 + 64; // Padding
+
+// Adds amount to the DepositRecord of owner and checks it against
+// max_per_wallet. Without a cap nothing is tracked, so the record is only
+// created, and rent only charged, for GSOs that have one.
+pub fn record_deposit<'info>(
+    gso_state: &Account<'info, GSOState>,
+    deposit_record: &AccountInfo<'info>,
+    deposit_record_bump: u8,
+    owner: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    amount: u64,
+) -> Result<()> {
+    let max_per_wallet: u64 = gso_state.max_per_wallet;
+    if max_per_wallet == 0 {
+        return Ok(());
+    }
+
+    let mut record: DepositRecord = if deposit_record.data_is_empty() {
+        let gso_state_key: Pubkey = gso_state.key();
+        let owner_key: Pubkey = owner.key();
+        let seeds: &[&[u8]] = &[
+            DEPOSIT_RECORD_SEED,
+            &gso_state_key.to_bytes(),
+            &owner_key.to_bytes(),
+            &[deposit_record_bump],
+        ];
+        // Same as init_if_needed. Anyone can send lamports to the address
+        // first, so create_account can not be used when there are some.
+        let rent: u64 = Rent::get()?.minimum_balance(DEPOSIT_RECORD_SPACE);
+        let lamports: u64 = deposit_record.lamports();
+        if lamports == 0 {
+            anchor_lang::system_program::create_account(
+                CpiContext::new_with_signer(
+                    system_program.clone(),
+                    anchor_lang::system_program::CreateAccount {
+                        from: owner.clone(),
+                        to: deposit_record.clone(),
+                    },
+                    &[seeds],
+                ),
+                rent,
+                DEPOSIT_RECORD_SPACE as u64,
+                &crate::ID,
+            )?;
+        } else {
+            if rent > lamports {
+                anchor_lang::system_program::transfer(
+                    CpiContext::new(
+                        system_program.clone(),
+                        anchor_lang::system_program::Transfer {
+                            from: owner.clone(),
+                            to: deposit_record.clone(),
+                        },
+                    ),
+                    rent - lamports,
+                )?;
+            }
+            anchor_lang::system_program::allocate(
+                CpiContext::new_with_signer(
+                    system_program.clone(),
+                    anchor_lang::system_program::Allocate {
+                        account_to_allocate: deposit_record.clone(),
+                    },
+                    &[seeds],
+                ),
+                DEPOSIT_RECORD_SPACE as u64,
+            )?;
+            anchor_lang::system_program::assign(
+                CpiContext::new_with_signer(
+                    system_program.clone(),
+                    anchor_lang::system_program::Assign {
+                        account_to_assign: deposit_record.clone(),
+                    },
+                    &[seeds],
+                ),
+                &crate::ID,
+            )?;
+        }
+        DepositRecord {
+            gso_state: gso_state_key,
+            owner: owner_key,
+            amount_deposited: 0,
+            bump: deposit_record_bump,
+        }
+    } else {
+        DepositRecord::try_deserialize(&mut &deposit_record.try_borrow_data()?[..])?
+    };
+
+    record.amount_deposited = unwrap_int!(record.amount_deposited.checked_add(amount));
+    invariant!(record.amount_deposited <= max_per_wallet, WalletCapExceeded);
+    record.try_serialize(&mut &mut deposit_record.try_borrow_mut_data()?[..])?;
+    Ok(())
+}
+
+// One per project name. Owns the name so only its authority can config
+// periods, and keeps track of the latest period so clients can find it.
+#[account]
+pub struct Project {
+    pub authority: Pubkey,
+    pub project_name: String,
+
+    // Period number of the most recent config. 0 until the first period.
+    pub latest_period: u64,
+
+    // Defaults for clients to use when configuring the next period. Not
+    // enforced on chain.
+    pub default_lockup_ratio_tokens_per_million: u64,
+    pub default_lot_size: u64,
+    pub default_strike: u64,
+
+    pub bump: u8,
+
+    // Set by propose_project_authority until the new authority accepts.
+    pub pending_authority: Pubkey,
+}
+
+// Checked arithmetic for ratio, reward and penalty math. Everything is done in
+// u128 so that tokens like BONK which use all the bits of a u64 do not
+// overflow in the intermediate product, then converted back to u64 with an
+// explicit error instead of truncating.
+pub mod math {
+    use crate::common::*;
+    use crate::errors::ErrorCode;
+    use std::convert::TryFrom;
+
+    // Denominator for lockup_ratio_tokens_per_million.
+    pub const LOCKUP_RATIO_DENOMINATOR: u64 = 1_000_000;
+
+    // Denominator for values in basis points.
+    pub const BPS_DENOMINATOR: u64 = 10_000;
+
+    pub fn to_u64(value: u128) -> Result<u64> {
+        u64::try_from(value).map_err(|_| error!(ErrorCode::MathOverflow))
+    }
+
+    // value * numerator / denominator, rounded down.
+    pub fn mul_div_floor(value: u64, numerator: u64, denominator: u64) -> Result<u64> {
+        let product: u128 = (value as u128)
+            .checked_mul(numerator as u128)
+            .ok_or_else(|| error!(ErrorCode::MathOverflow))?;
+        let quotient: u128 = product
+            .checked_div(denominator as u128)
+            .ok_or_else(|| error!(ErrorCode::MathOverflow))?;
+        to_u64(quotient)
+    }
+
+    // value * numerator / denominator, rounded up.
+    pub fn mul_div_ceil(value: u64, numerator: u64, denominator: u64) -> Result<u64> {
+        let product: u128 = (value as u128)
+            .checked_mul(numerator as u128)
+            .ok_or_else(|| error!(ErrorCode::MathOverflow))?;
+        let rounded: u128 = product
+            .checked_add((denominator as u128).saturating_sub(1))
+            .ok_or_else(|| error!(ErrorCode::MathOverflow))?;
+        let quotient: u128 = rounded
+            .checked_div(denominator as u128)
+            .ok_or_else(|| error!(ErrorCode::MathOverflow))?;
+        to_u64(quotient)
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum Rounding {
+        Down,
+        Up,
+    }
+
+    // Lockup ratio of whole option base tokens per whole lockup token, kept
+    // as a fraction so that it is not limited to per million precision.
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub struct Ratio {
+        pub numerator: u128,
+        pub denominator: u128,
+    }
+
+    impl Ratio {
+        pub fn per_million(tokens_per_million: u64) -> Ratio {
+            Ratio {
+                numerator: tokens_per_million as u128,
+                denominator: LOCKUP_RATIO_DENOMINATOR as u128,
+            }
+        }
+
+        // Scales the ratio by scale_numerator / scale_denominator, reduced so
+        // that chained scaling does not overflow as quickly.
+        pub fn scale(&self, scale_numerator: u128, scale_denominator: u128) -> Result<Ratio> {
+            let numerator: u128 = self
+                .numerator
+                .checked_mul(scale_numerator)
+                .ok_or_else(|| error!(ErrorCode::MathOverflow))?;
+            let denominator: u128 = self
+                .denominator
+                .checked_mul(scale_denominator)
+                .ok_or_else(|| error!(ErrorCode::MathOverflow))?;
+            let divisor: u128 = gcd(numerator, denominator).max(1);
+            Ok(Ratio {
+                numerator: numerator / divisor,
+                denominator: denominator / divisor,
+            })
+        }
+    }
+
+    fn gcd(mut a: u128, mut b: u128) -> u128 {
+        while b != 0 {
+            let remainder: u128 = a % b;
+            a = b;
+            b = remainder;
+        }
+        a
+    }
+
+    // value * numerator / denominator in u128 with the given rounding.
+    fn mul_div_u128(
+        value: u64,
+        numerator: u128,
+        denominator: u128,
+        rounding: Rounding,
+    ) -> Result<u64> {
+        let product: u128 = (value as u128)
+            .checked_mul(numerator)
+            .ok_or_else(|| error!(ErrorCode::MathOverflow))?;
+        let rounded: u128 = match rounding {
+            Rounding::Down => product,
+            Rounding::Up => product
+                .checked_add(denominator.saturating_sub(1))
+                .ok_or_else(|| error!(ErrorCode::MathOverflow))?,
+        };
+        let quotient: u128 = rounded
+            .checked_div(denominator)
+            .ok_or_else(|| error!(ErrorCode::MathOverflow))?;
+        to_u64(quotient)
+    }
+
+    // Lockup ratio as option base atoms per lockup atom. The ratio is in whole
+    // tokens, so the difference in decimals between the two mints is folded
+    // into the fraction.
+    fn atom_ratio(ratio: Ratio, lockup_decimals: u8, option_base_decimals: u8) -> Result<Ratio> {
+        let scale = |decimals: u8| {
+            10u128
+                .checked_pow(decimals as u32)
+                .ok_or_else(|| error!(ErrorCode::MathOverflow))
+        };
+        if option_base_decimals >= lockup_decimals {
+            ratio.scale(scale(option_base_decimals - lockup_decimals)?, 1)
+        } else {
+            ratio.scale(1, scale(lockup_decimals - option_base_decimals)?)
+        }
+    }
+
+    // Option base atoms earned by locking amount atoms of the lockup mint.
+    pub fn lockup_to_options(
+        amount: u64,
+        ratio: Ratio,
+        lockup_decimals: u8,
+        option_base_decimals: u8,
+        rounding: Rounding,
+    ) -> Result<u64> {
+        let atom_ratio: Ratio = atom_ratio(ratio, lockup_decimals, option_base_decimals)?;
+        mul_div_u128(
+            amount,
+            atom_ratio.numerator,
+            atom_ratio.denominator,
+            rounding,
+        )
+    }
+
+    // Smallest amount of lockup atoms that earns options, so rounded up.
+    pub fn options_to_lockup(
+        options: u64,
+        ratio: Ratio,
+        lockup_decimals: u8,
+        option_base_decimals: u8,
+    ) -> Result<u64> {
+        let atom_ratio: Ratio = atom_ratio(ratio, lockup_decimals, option_base_decimals)?;
+        mul_div_u128(
+            options,
+            atom_ratio.denominator,
+            atom_ratio.numerator,
+            Rounding::Up,
+        )
+    }
+
+    // Largest multiple of lot_size that is not more than value.
+    pub fn round_down_to_lot(value: u64, lot_size: u64) -> Result<u64> {
+        let num_lots: u64 = value
+            .checked_div(lot_size)
+            .ok_or_else(|| error!(ErrorCode::MathOverflow))?;
+        num_lots
+            .checked_mul(lot_size)
+            .ok_or_else(|| error!(ErrorCode::MathOverflow))
+    }
+
+    // Ratio for now_ts given a schedule that goes from start_ratio at start_ts
+    // to end_ratio at end_ts. Times outside the window are clamped.
+    pub fn scheduled_ratio(
+        schedule: u8,
+        start_ratio: u64,
+        end_ratio: u64,
+        steps: u64,
+        start_ts: u64,
+        end_ts: u64,
+        now_ts: u64,
+    ) -> Result<u64> {
+        if schedule == RATIO_SCHEDULE_FLAT || end_ts <= start_ts {
+            return Ok(start_ratio);
+        }
+        let duration: u64 = end_ts - start_ts;
+        let elapsed: u64 = now_ts.saturating_sub(start_ts).min(duration);
+        let ratio_range: u64 = start_ratio
+            .checked_sub(end_ratio)
+            .ok_or_else(|| error!(ErrorCode::MathOverflow))?;
+
+        let decay: u64 = if schedule == RATIO_SCHEDULE_LINEAR {
+            mul_div_floor(ratio_range, elapsed, duration)?
+        } else {
+            // Step k of steps gets k / (steps - 1) of the range, so the first
+            // step is the start ratio and the last step is the end ratio.
+            let last_step: u64 = steps
+                .checked_sub(1)
+                .ok_or_else(|| error!(ErrorCode::MathOverflow))?;
+            let step: u64 = mul_div_floor(steps, elapsed, duration)?.min(last_step);
+            mul_div_floor(ratio_range, step, last_step)?
+        };
+        start_ratio
+            .checked_sub(decay)
+            .ok_or_else(|| error!(ErrorCode::MathOverflow))
+    }
+
+    // How much more can be slashed from a vault given what was slashed from
+    // it already. The cap is on the vault as it was before any slashing.
+    pub fn remaining_slash_allowance(
+        vault_amount: u64,
+        total_slashed: u64,
+        max_slash_bps: u64,
+    ) -> Result<u64> {
+        let unslashed_amount: u64 = vault_amount
+            .checked_add(total_slashed)
+            .ok_or_else(|| error!(ErrorCode::MathOverflow))?;
+        let max_total_slashed: u64 =
+            mul_div_floor(unslashed_amount, max_slash_bps, BPS_DENOMINATOR)?;
+        Ok(max_total_slashed.saturating_sub(total_slashed))
+    }
+
+    // Part of total that has vested at now_ts on a linear curve from start_ts
+    // to end_ts.
+    pub fn vested_amount(total: u64, start_ts: u64, end_ts: u64, now_ts: u64) -> Result<u64> {
+        if now_ts >= end_ts {
+            return Ok(total);
+        }
+        if now_ts <= start_ts {
+            return Ok(0);
+        }
+        mul_div_floor(total, now_ts - start_ts, end_ts - start_ts)
+    }
+
+    // Early unstake penalty in basis points at now_ts. With decay the penalty
+    // goes linearly from max_penalty_bps at start_ts to 0 at end_ts.
+    pub fn early_unstake_penalty_bps(
+        max_penalty_bps: u64,
+        decay: bool,
+        start_ts: u64,
+        end_ts: u64,
+        now_ts: u64,
+    ) -> Result<u64> {
+        if !decay {
+            return Ok(max_penalty_bps);
+        }
+        let decayed: u64 = vested_amount(max_penalty_bps, start_ts, end_ts, now_ts)?;
+        max_penalty_bps
+            .checked_sub(decayed)
+            .ok_or_else(|| error!(ErrorCode::MathOverflow))
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        const NINE_DECIMALS: u64 = 1_000_000_000;
+        const BONK_DECIMALS: u64 = 100_000;
+
+        fn is_overflow(result: Result<u64>) -> bool {
+            match result {
+                Err(Error::AnchorError(err)) => err.error_name == "MathOverflow",
+                _ => false,
+            }
+        }
+
+        fn is_overflow_ratio(result: Result<Ratio>) -> bool {
+            is_overflow(result.map(|_| 0))
+        }
+
+        #[test]
+        fn test_to_u64() {
+            assert_eq!(to_u64(u64::MAX as u128).unwrap(), u64::MAX);
+            assert!(is_overflow(to_u64(u64::MAX as u128 + 1)));
+        }
+
+        #[test]
+        fn test_mul_div_floor_nine_decimals() {
+            // 1 token at 200k per million is 0.2 tokens.
+            assert_eq!(
+                mul_div_floor(NINE_DECIMALS, 200_000, LOCKUP_RATIO_DENOMINATOR).unwrap(),
+                200_000_000
+            );
+            // Rounds down.
+            assert_eq!(
+                mul_div_floor(4, 200_000, LOCKUP_RATIO_DENOMINATOR).unwrap(),
+                0
+            );
+            assert_eq!(
+                mul_div_floor(5, 200_000, LOCKUP_RATIO_DENOMINATOR).unwrap(),
+                1
+            );
+            // Intermediate product is above u64 but the result fits.
+            assert_eq!(
+                mul_div_floor(u64::MAX, 1_000_000, LOCKUP_RATIO_DENOMINATOR).unwrap(),
+                u64::MAX
+            );
+        }
+
+        #[test]
+        fn test_mul_div_floor_bonk() {
+            // Roughly the entire BONK supply, 100 trillion tokens.
+            let bonk_supply: u64 = 100_000_000_000_000 * BONK_DECIMALS;
+            assert_eq!(
+                mul_div_floor(bonk_supply, 1_000_000, LOCKUP_RATIO_DENOMINATOR).unwrap(),
+                bonk_supply
+            );
+            // A ratio of more than one option per token no longer fits.
+            assert!(is_overflow(mul_div_floor(
+                bonk_supply,
+                2_000_000,
+                LOCKUP_RATIO_DENOMINATOR
+            )));
+            // Largest ratio that still fits for the full supply.
+            let max_ratio: u64 = ((u64::MAX as u128 * LOCKUP_RATIO_DENOMINATOR as u128)
+                / bonk_supply as u128) as u64;
+            assert!(mul_div_floor(bonk_supply, max_ratio, LOCKUP_RATIO_DENOMINATOR).is_ok());
+            assert!(is_overflow(mul_div_floor(
+                bonk_supply,
+                max_ratio + 1,
+                LOCKUP_RATIO_DENOMINATOR
+            )));
+        }
+
+        #[test]
+        fn test_mul_div_floor_overflow() {
+            assert!(is_overflow(mul_div_floor(u64::MAX, u64::MAX, 1)));
+            assert!(is_overflow(mul_div_floor(1, 1, 0)));
+        }
+
+        #[test]
+        fn test_mul_div_ceil() {
+            assert_eq!(mul_div_ceil(3, 1, 3).unwrap(), 1);
+            assert_eq!(mul_div_ceil(4, 1, 3).unwrap(), 2);
+            assert_eq!(mul_div_ceil(0, 1, 3).unwrap(), 0);
+            assert_eq!(
+                mul_div_ceil(200_000_000, LOCKUP_RATIO_DENOMINATOR, 200_000).unwrap(),
+                NINE_DECIMALS
+            );
+            assert_eq!(mul_div_ceil(u64::MAX, 1, 1).unwrap(), u64::MAX);
+            assert!(is_overflow(mul_div_ceil(u64::MAX, 2, 1)));
+            assert!(is_overflow(mul_div_ceil(1, 1, 0)));
+        }
+
+        #[test]
+        fn test_lockup_to_options_same_decimals() {
+            // Same decimals is the same as applying the ratio to atoms.
+            assert_eq!(
+                lockup_to_options(
+                    NINE_DECIMALS,
+                    Ratio::per_million(200_000),
+                    9,
+                    9,
+                    Rounding::Down
+                )
+                .unwrap(),
+                mul_div_floor(NINE_DECIMALS, 200_000, LOCKUP_RATIO_DENOMINATOR).unwrap()
+            );
+            // States from before decimals were stored have both at 0.
+            assert_eq!(
+                lockup_to_options(5, Ratio::per_million(200_000), 0, 0, Rounding::Down).unwrap(),
+                1
+            );
+            assert_eq!(
+                lockup_to_options(4, Ratio::per_million(200_000), 0, 0, Rounding::Down).unwrap(),
+                0
+            );
+            assert_eq!(
+                lockup_to_options(4, Ratio::per_million(200_000), 0, 0, Rounding::Up).unwrap(),
+                1
+            );
+        }
+
+        #[test]
+        fn test_lockup_to_options_mixed_decimals() {
+            // 1 whole 6 decimal token at 0.2 is 0.2 whole 9 decimal tokens.
+            assert_eq!(
+                lockup_to_options(1_000_000, Ratio::per_million(200_000), 6, 9, Rounding::Down)
+                    .unwrap(),
+                200_000_000
+            );
+            // And the other way around.
+            assert_eq!(
+                lockup_to_options(
+                    NINE_DECIMALS,
+                    Ratio::per_million(200_000),
+                    9,
+                    6,
+                    Rounding::Down
+                )
+                .unwrap(),
+                200_000
+            );
+            // Less than an atom of the option base mint rounds down or up.
+            assert_eq!(
+                lockup_to_options(4_999, Ratio::per_million(200_000), 9, 6, Rounding::Down)
+                    .unwrap(),
+                0
+            );
+            assert_eq!(
+                lockup_to_options(4_999, Ratio::per_million(200_000), 9, 6, Rounding::Up).unwrap(),
+                1
+            );
+            assert_eq!(
+                lockup_to_options(5_000, Ratio::per_million(200_000), 9, 6, Rounding::Down)
+                    .unwrap(),
+                1
+            );
+            // BONK into a 9 decimal option base.
+            assert_eq!(
+                lockup_to_options(
+                    BONK_DECIMALS,
+                    Ratio::per_million(1_000_000),
+                    5,
+                    9,
+                    Rounding::Down
+                )
+                .unwrap(),
+                NINE_DECIMALS
+            );
+        }
+
+        #[test]
+        fn test_options_to_lockup_mixed_decimals() {
+            assert_eq!(
+                options_to_lockup(200_000_000, Ratio::per_million(200_000), 6, 9).unwrap(),
+                1_000_000
+            );
+            assert_eq!(
+                options_to_lockup(1, Ratio::per_million(200_000), 9, 6).unwrap(),
+                5_000
+            );
+            // Rounds up so the lockup always earns the options.
+            assert_eq!(
+                options_to_lockup(1, Ratio::per_million(300_000), 6, 9).unwrap(),
+                1
+            );
+            assert_eq!(
+                lockup_to_options(
+                    options_to_lockup(1_000, Ratio::per_million(300_000), 9, 6).unwrap(),
+                    Ratio::per_million(300_000),
+                    9,
+                    6,
+                    Rounding::Down
+                )
+                .unwrap(),
+                1_000
+            );
+        }
+
+        #[test]
+        fn test_lockup_to_options_overflow() {
+            assert!(is_overflow(lockup_to_options(
+                u64::MAX,
+                Ratio::per_million(LOCKUP_RATIO_DENOMINATOR),
+                0,
+                18,
+                Rounding::Down
+            )));
+            assert!(is_overflow(lockup_to_options(
+                1,
+                Ratio::per_million(1),
+                0,
+                255,
+                Rounding::Down
+            )));
+            assert!(is_overflow(options_to_lockup(
+                1,
+                Ratio::per_million(0),
+                6,
+                6
+            )));
+        }
+
+        #[test]
+        fn test_rational_ratio() {
+            // One option token per billion staked, below per million precision.
+            let ratio = Ratio {
+                numerator: 1,
+                denominator: 1_000_000_000,
+            };
+            assert_eq!(
+                lockup_to_options(NINE_DECIMALS, ratio, 6, 6, Rounding::Down).unwrap(),
+                1
+            );
+            assert_eq!(
+                lockup_to_options(NINE_DECIMALS - 1, ratio, 6, 6, Rounding::Down).unwrap(),
+                0
+            );
+            assert_eq!(
+                lockup_to_options(NINE_DECIMALS - 1, ratio, 6, 6, Rounding::Up).unwrap(),
+                1
+            );
+            assert_eq!(options_to_lockup(1, ratio, 6, 6).unwrap(), NINE_DECIMALS);
+            // A third is exact where per million would round.
+            let third = Ratio {
+                numerator: 1,
+                denominator: 3,
+            };
+            assert_eq!(
+                lockup_to_options(3_000_000, third, 6, 6, Rounding::Down).unwrap(),
+                1_000_000
+            );
+            assert_eq!(
+                lockup_to_options(3_000_000, Ratio::per_million(333_333), 6, 6, Rounding::Down)
+                    .unwrap(),
+                999_999
+            );
+            assert_eq!(options_to_lockup(1, third, 6, 9).unwrap(), 1);
+            assert_eq!(options_to_lockup(1_000, third, 9, 6).unwrap(), 3_000_000);
+        }
+
+        #[test]
+        fn test_ratio_scale() {
+            let ratio = Ratio {
+                numerator: 2,
+                denominator: 3,
+            };
+            assert_eq!(
+                ratio.scale(3, 4).unwrap(),
+                Ratio {
+                    numerator: 1,
+                    denominator: 2,
+                }
+            );
+            assert_eq!(
+                Ratio::per_million(200_000).scale(1, 1).unwrap(),
+                Ratio {
+                    numerator: 1,
+                    denominator: 5,
+                }
+            );
+            assert!(is_overflow_ratio(ratio.scale(u128::MAX, 1)));
+        }
+
+        #[test]
+        fn test_round_down_to_lot() {
+            assert_eq!(round_down_to_lot(1_999, 1_000).unwrap(), 1_000);
+            assert_eq!(round_down_to_lot(999, 1_000).unwrap(), 0);
+            assert_eq!(round_down_to_lot(u64::MAX, 1).unwrap(), u64::MAX);
+            assert!(is_overflow(round_down_to_lot(1, 0)));
+        }
+
+        #[test]
+        fn test_scheduled_ratio_flat() {
+            for now_ts in [0, 100, 150, 200, 1_000] {
+                assert_eq!(
+                    scheduled_ratio(RATIO_SCHEDULE_FLAT, 500, 100, 0, 100, 200, now_ts).unwrap(),
+                    500
+                );
+            }
+        }
+
+        #[test]
+        fn test_scheduled_ratio_linear() {
+            let ratio = |now_ts| {
+                scheduled_ratio(RATIO_SCHEDULE_LINEAR, 500, 100, 0, 100, 200, now_ts).unwrap()
+            };
+            assert_eq!(ratio(0), 500);
+            assert_eq!(ratio(100), 500);
+            assert_eq!(ratio(125), 400);
+            assert_eq!(ratio(150), 300);
+            assert_eq!(ratio(200), 100);
+            assert_eq!(ratio(1_000), 100);
+        }
+
+        #[test]
+        fn test_scheduled_ratio_stepwise() {
+            let ratio = |now_ts| {
+                scheduled_ratio(RATIO_SCHEDULE_STEPWISE, 500, 100, 3, 100, 400, now_ts).unwrap()
+            };
+            assert_eq!(ratio(100), 500);
+            assert_eq!(ratio(199), 500);
+            assert_eq!(ratio(200), 300);
+            assert_eq!(ratio(299), 300);
+            assert_eq!(ratio(300), 100);
+            assert_eq!(ratio(400), 100);
+        }
+
+        #[test]
+        fn test_scheduled_ratio_invalid() {
+            // Increasing ratio and a stepwise schedule without steps.
+            assert!(is_overflow(scheduled_ratio(
+                RATIO_SCHEDULE_LINEAR,
+                100,
+                500,
+                0,
+                100,
+                200,
+                150
+            )));
+            assert!(is_overflow(scheduled_ratio(
+                RATIO_SCHEDULE_STEPWISE,
+                500,
+                100,
+                0,
+                100,
+                200,
+                150
+            )));
+        }
+
+        #[test]
+        fn test_remaining_slash_allowance() {
+            // 10% of 1_000.
+            assert_eq!(remaining_slash_allowance(1_000, 0, 1_000).unwrap(), 100);
+            // After slashing 60 there is 40 left out of the original 1_000.
+            assert_eq!(remaining_slash_allowance(940, 60, 1_000).unwrap(), 40);
+            assert_eq!(remaining_slash_allowance(900, 100, 1_000).unwrap(), 0);
+            // Unstaking shrinks the vault and the allowance with it.
+            assert_eq!(remaining_slash_allowance(400, 60, 1_000).unwrap(), 0);
+            assert_eq!(remaining_slash_allowance(1_000, 0, 0).unwrap(), 0);
+            assert_eq!(
+                remaining_slash_allowance(1_000, 0, BPS_DENOMINATOR).unwrap(),
+                1_000
+            );
+        }
+
+        #[test]
+        fn test_vested_amount() {
+            assert_eq!(vested_amount(1_000, 100, 200, 50).unwrap(), 0);
+            assert_eq!(vested_amount(1_000, 100, 200, 100).unwrap(), 0);
+            assert_eq!(vested_amount(1_000, 100, 200, 125).unwrap(), 250);
+            assert_eq!(vested_amount(1_000, 100, 200, 199).unwrap(), 990);
+            assert_eq!(vested_amount(1_000, 100, 200, 200).unwrap(), 1_000);
+            assert_eq!(vested_amount(u64::MAX, 0, 3, 1).unwrap(), u64::MAX / 3);
+        }
+
+        #[test]
+        fn test_early_unstake_penalty_bps() {
+            assert_eq!(
+                early_unstake_penalty_bps(500, false, 100, 200, 150).unwrap(),
+                500
+            );
+            assert_eq!(
+                early_unstake_penalty_bps(500, true, 100, 200, 50).unwrap(),
+                500
+            );
+            assert_eq!(
+                early_unstake_penalty_bps(500, true, 100, 200, 150).unwrap(),
+                250
+            );
+            assert_eq!(
+                early_unstake_penalty_bps(500, true, 100, 200, 199).unwrap(),
+                5
+            );
+            assert_eq!(
+                early_unstake_penalty_bps(500, true, 100, 200, 200).unwrap(),
+                0
+            );
+        }
+
+        #[test]
+        fn test_lockup_round_trip() {
+            // The amount locked for a number of options always earns at least
+            // that many options again, for both 9 and 5 decimal mints.
+            for (amount, ratio) in [
+                (NINE_DECIMALS + 7, 333_333),
+                (123 * NINE_DECIMALS, 1),
+                (BONK_DECIMALS - 1, 999_999),
+                (u64::MAX / 2, 1_999_999),
+            ] {
+                let options: u64 = mul_div_floor(amount, ratio, LOCKUP_RATIO_DENOMINATOR).unwrap();
+                let lockup: u64 = mul_div_ceil(options, LOCKUP_RATIO_DENOMINATOR, ratio).unwrap();
+                assert!(lockup <= amount);
+                assert_eq!(
+                    mul_div_floor(lockup, ratio, LOCKUP_RATIO_DENOMINATOR).unwrap(),
+                    options
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tier_zero_seed_matches_legacy_address() {
+        let gso_state: Pubkey = Pubkey::new_unique();
+        let (legacy, legacy_bump) =
+            Pubkey::find_program_address(&[BASE_VAULT_SEED, &gso_state.to_bytes()], &crate::ID);
+        let (tier_zero, tier_zero_bump) = Pubkey::find_program_address(
+            &[BASE_VAULT_SEED, &gso_state.to_bytes(), tier_seed(&0)],
+            &crate::ID,
+        );
+        assert_eq!(legacy, tier_zero);
+        assert_eq!(legacy_bump, tier_zero_bump);
+
+        let (tier_one, _tier_one_bump) = Pubkey::find_program_address(
+            &[BASE_VAULT_SEED, &gso_state.to_bytes(), tier_seed(&1)],
+            &crate::ID,
+        );
+        assert_ne!(legacy, tier_one);
+    }
+}
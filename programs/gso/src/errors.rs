@@ -1,7 +1,8 @@
 use anchor_lang::prelude::*;
 
-// Most errors are just propagated through the SO calls and token program. So
-// really the only errors are the ones in unstaking.
+// Errors for the checks GSO makes itself. Failures in the SO calls, Metaplex
+// and the token program are propagated with their own errors. New variants go
+// at the end so that existing codes do not change.
 
 #[error_code]
 pub enum ErrorCode {
@@ -9,4 +10,94 @@ pub enum ErrorCode {
     NotYetExpired,
     #[msg("Lockup end cannot be before subscription period end")]
     InvalidLockupEnd,
+    #[msg("Cannot close while xTokens are still outstanding")]
+    XTokensOutstanding,
+    #[msg("Cannot close while the base vault still holds tokens")]
+    VaultNotEmpty,
+    #[msg("This action is paused by the GSO authority")]
+    Paused,
+    #[msg("Stake would exceed the total lockup cap for this GSO")]
+    TotalCapExceeded,
+    #[msg("Stake would exceed the per wallet cap for this GSO")]
+    WalletCapExceeded,
+    #[msg("Subscription period has ended")]
+    SubscriptionEnded,
+    #[msg("Subscription period has not started")]
+    SubscriptionNotStarted,
+    #[msg("Subscription start must be before subscription period end")]
+    InvalidSubscriptionStart,
+    #[msg("Stake is too small to earn a single lot of options")]
+    StakeTooSmall,
+    #[msg("Math overflow")]
+    MathOverflow,
+    #[msg("Lockup ratio schedule is invalid")]
+    InvalidRatioSchedule,
+    #[msg("Lockup tier does not exist")]
+    InvalidTier,
+    #[msg("Maximum number of lockup tiers reached")]
+    TooManyTiers,
+    #[msg("Strike index does not exist")]
+    InvalidStrikeIndex,
+    #[msg("Maximum number of strikes reached")]
+    TooManyStrikes,
+    #[msg("Slashing parameters are invalid")]
+    InvalidSlashParams,
+    #[msg("Slashing is not enabled for this GSO")]
+    SlashingDisabled,
+    #[msg("Slash would exceed the maximum slash for this GSO")]
+    SlashTooLarge,
+    #[msg("No pending slash for this tier")]
+    NoPendingSlash,
+    #[msg("Slash is still timelocked")]
+    SlashTimelocked,
+    #[msg("Borrow would exceed the credit limit for this GSO")]
+    CreditLimitExceeded,
+    #[msg("Cannot borrow after the lockup period end")]
+    BorrowAfterLockupEnd,
+    #[msg("Borrowed tokens have not been repaid to the base vault")]
+    DebtOutstanding,
+    #[msg("GSO is not in default")]
+    NotInDefault,
+    #[msg("Vesting end must be after lockup end")]
+    InvalidVestingEnd,
+    #[msg("Unstake is more than has vested so far")]
+    NotYetVested,
+    #[msg("Options have expired")]
+    OptionsExpired,
+    #[msg("Invalid early unstake penalty")]
+    InvalidPenaltyParams,
+    #[msg("Rollover must be into a later period of the same project and lockup mint")]
+    RolloverMismatch,
+    #[msg("Period must be the next one for the project")]
+    InvalidPeriodNum,
+    #[msg("Option expiration cannot be before subscription period end")]
+    InvalidOptionExpiration,
+    #[msg("Strike must be above zero")]
+    InvalidStrike,
+    #[msg("Lot size must be above zero")]
+    InvalidLotSize,
+    #[msg("Number of tokens must be a multiple of the lot size")]
+    InvalidNumTokens,
+    #[msg("Lockup ratio is zero or too large")]
+    InvalidLockupRatio,
+    #[msg("Project name must be 1 to 23 bytes and fit in the SO name with the period")]
+    InvalidProjectName,
+    #[msg("Token account is for the wrong mint")]
+    InvalidTokenMint,
+    #[msg("Lockup mint is already stored for this GSO")]
+    AlreadyMigrated,
+    #[msg("Cannot close until the staking options vault is withdrawn")]
+    OptionsNotWithdrawn,
+    #[msg("Pause flags include undefined bits")]
+    InvalidPauseFlags,
+    #[msg("Lockup has ended, use unstake")]
+    LockupEnded,
+    #[msg("Project name is used by GSOs of another authority")]
+    ProjectNameTaken,
+    #[msg("Cannot slash until the subscription period ends")]
+    SlashDuringSubscription,
+    #[msg(
+        "Cannot unstake early with a penalty kept in the vault until the subscription period ends"
+    )]
+    PenaltyDuringSubscription,
 }
@@ -0,0 +1,62 @@
+use anchor_lang::prelude::*;
+
+// Events for off-chain indexers. Every event has the GSOState it is for and
+// the unix timestamp of the slot it happened in.
+
+#[event]
+pub struct Configured {
+    pub gso_state: Pubkey,
+    pub authority: Pubkey,
+    pub project_name: String,
+    pub period_num: u64,
+    pub staking_options_state: Pubkey,
+    pub subscription_period_end: u64,
+    pub lockup_period_end: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct Staked {
+    pub gso_state: Pubkey,
+    pub user: Pubkey,
+    pub tier: u8,
+    pub strike_index: u8,
+    // Tokens locked, which is also the number of xTokens minted.
+    pub amount: u64,
+    pub options_issued: u64,
+    pub lockup_ratio_tokens_per_million: u64,
+    // Options per token the stake was issued at, which is not always
+    // expressible per million when the GSO has a lockup_ratio_den.
+    pub lockup_ratio_num: u128,
+    pub lockup_ratio_den: u128,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct Unstaked {
+    pub gso_state: Pubkey,
+    pub user: Pubkey,
+    pub tier: u8,
+    // xTokens burned.
+    pub amount: u64,
+    // Tokens sent back to the user, after any slashing and penalty.
+    pub amount_returned: u64,
+    pub penalty: u64,
+    // Options burned by early_unstake. 0 for every other unstake.
+    pub options_burned: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct Withdrawn {
+    pub gso_state: Pubkey,
+    pub authority: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TokensNamed {
+    pub gso_state: Pubkey,
+    pub timestamp: i64,
+}
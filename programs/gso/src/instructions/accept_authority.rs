@@ -0,0 +1,29 @@
+pub use crate::common::*;
+pub use crate::*;
+
+pub fn accept_authority(ctx: Context<GSOAcceptAuthority>) -> Result<()> {
+    msg!(
+        "GSO Accept Authority {}",
+        ctx.accounts.pending_authority.key()
+    );
+
+    ctx.accounts.gso_state.authority = ctx.accounts.pending_authority.key();
+    ctx.accounts.gso_state.pending_authority = Pubkey::default();
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction()]
+pub struct GSOAcceptAuthority<'info> {
+    // Must sign so that only a key that is actually controlled can become the
+    // authority.
+    pub pending_authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [GSO_STATE_SEED, &gso_state.period_num.to_be_bytes(), &gso_state.project_name.as_bytes()],
+        bump = gso_state.gso_state_bump,
+        constraint = gso_state.pending_authority.key() == pending_authority.key())]
+    pub gso_state: Box<Account<'info, GSOState>>,
+}
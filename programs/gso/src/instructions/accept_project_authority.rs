@@ -0,0 +1,29 @@
+pub use crate::common::*;
+pub use crate::*;
+
+pub fn accept_project_authority(ctx: Context<GSOAcceptProjectAuthority>) -> Result<()> {
+    msg!(
+        "GSO Accept Project Authority {}",
+        ctx.accounts.pending_authority.key()
+    );
+
+    ctx.accounts.project.authority = ctx.accounts.pending_authority.key();
+    ctx.accounts.project.pending_authority = Pubkey::default();
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction()]
+pub struct GSOAcceptProjectAuthority<'info> {
+    // Must sign so that only a key that is actually controlled can become the
+    // authority.
+    pub pending_authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [PROJECT_SEED, project.project_name.as_bytes()],
+        bump = project.bump,
+        constraint = project.pending_authority.key() == pending_authority.key())]
+    pub project: Box<Account<'info, Project>>,
+}
@@ -0,0 +1,88 @@
+use anchor_spl::token::Token;
+use staking_options::program::StakingOptions as StakingOptionsProgram;
+
+pub use crate::common::*;
+pub use crate::errors::ErrorCode;
+pub use crate::*;
+
+pub fn add_strike(
+    ctx: Context<GSOAddStrike>,
+    strike: u64,
+    lockup_ratio_tokens_per_million: u64,
+) -> Result<()> {
+    msg!("GSO Add Strike {}", strike);
+
+    invariant!(
+        ctx.accounts.gso_state.strikes.len() < MAX_STRIKES,
+        TooManyStrikes
+    );
+    validate_lockup_ratio(lockup_ratio_tokens_per_million)?;
+
+    msg!("SO Init Strike");
+    let so_init_strike_accounts = staking_options::cpi::accounts::InitStrikeWithPayer {
+        authority: ctx.accounts.so_authority.to_account_info(),
+        payer: ctx.accounts.authority.to_account_info(),
+        state: ctx.accounts.so_state.to_account_info(),
+        option_mint: ctx.accounts.so_option_mint.to_account_info(),
+        token_program: ctx.accounts.token_program.to_account_info(),
+        system_program: ctx.accounts.system_program.to_account_info(),
+        rent: ctx.accounts.rent.to_account_info(),
+    };
+    let cpi_program_init_strike = ctx.accounts.staking_options_program.to_account_info();
+
+    staking_options::cpi::init_strike_with_payer(
+        CpiContext::new_with_signer(
+            cpi_program_init_strike,
+            so_init_strike_accounts,
+            &[&[
+                SO_AUTHORITY_SEED,
+                &ctx.accounts.gso_state.key().to_bytes(),
+                &[ctx.accounts.gso_state.so_authority_bump],
+            ]],
+        ),
+        strike,
+    )?;
+
+    ctx.accounts.gso_state.strikes.push(StrikeLevel {
+        strike,
+        lockup_ratio_tokens_per_million,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(strike: u64)]
+pub struct GSOAddStrike<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [GSO_STATE_SEED, &gso_state.period_num.to_be_bytes(), &gso_state.project_name.as_bytes()],
+        bump = gso_state.gso_state_bump,
+        constraint = gso_state.authority.key() == authority.key())]
+    pub gso_state: Box<Account<'info, GSOState>>,
+
+    /// CHECK: Not dangerous. This is just a PDA, not a funded account.
+    #[account(mut,
+        seeds = [SO_AUTHORITY_SEED, &gso_state.key().to_bytes()],
+        bump = gso_state.so_authority_bump,
+    )]
+    pub so_authority: AccountInfo<'info>,
+
+    /// Seeds are verified in the CPI. Verified that this is the correct
+    /// so_state here.
+    #[account(mut, constraint = so_state.key() == gso_state.staking_options_state)]
+    pub so_state: Box<Account<'info, staking_options::State>>,
+
+    #[account(mut)]
+    /// CHECK: Not dangerous. Checked in CPI where it is initiailized.
+    pub so_option_mint: UncheckedAccount<'info>,
+
+    pub staking_options_program: Program<'info, StakingOptionsProgram>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
@@ -0,0 +1,85 @@
+use anchor_spl::token::{Mint, Token, TokenAccount};
+
+pub use crate::common::*;
+pub use crate::errors::ErrorCode;
+pub use crate::*;
+
+pub fn add_tier(
+    ctx: Context<GSOAddTier>,
+    tier: u8,
+    lockup_period_end: u64,
+    lockup_ratio_tokens_per_million: u64,
+) -> Result<()> {
+    msg!("GSO Add Tier {}", tier);
+
+    invariant!(ctx.accounts.gso_state.tiers.len() < MAX_TIERS, TooManyTiers);
+    invariant!(
+        tier as usize == ctx.accounts.gso_state.tiers.len() + 1,
+        InvalidTier
+    );
+    invariant!(
+        lockup_period_end >= ctx.accounts.gso_state.subscription_period_end,
+        InvalidLockupEnd
+    );
+    validate_lockup_ratio(lockup_ratio_tokens_per_million)?;
+
+    let x_base_mint_bump: u8 = *ctx.bumps.get("x_base_mint").unwrap();
+    let base_vault_bump: u8 = *ctx.bumps.get("base_vault").unwrap();
+    ctx.accounts.gso_state.tiers.push(LockupTier {
+        lockup_period_end,
+        lockup_ratio_tokens_per_million,
+        x_base_mint_bump,
+        base_vault_bump,
+        total_slashed: 0,
+        total_unstaked: 0,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(tier: u8)]
+pub struct GSOAddTier<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [GSO_STATE_SEED, &gso_state.period_num.to_be_bytes(), &gso_state.project_name.as_bytes()],
+        bump = gso_state.gso_state_bump,
+        constraint = gso_state.authority.key() == authority.key())]
+    pub gso_state: Box<Account<'info, GSOState>>,
+
+    /// The tier 0 vault, only used to find the lockup mint.
+    #[account(
+        seeds = [BASE_VAULT_SEED, &gso_state.key().to_bytes()],
+        bump = gso_state.base_vault_bump
+    )]
+    pub tier_zero_base_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(constraint = lockup_mint.key() == tier_zero_base_vault.mint)]
+    pub lockup_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        init,
+        payer = authority,
+        seeds = [X_GSO_SEED, &gso_state.key().to_bytes(), tier_seed(&tier)],
+        bump,
+        mint::decimals = lockup_mint.decimals,
+        mint::authority = x_base_mint)]
+    pub x_base_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        init,
+        payer = authority,
+        seeds = [BASE_VAULT_SEED, &gso_state.key().to_bytes(), tier_seed(&tier)],
+        token::mint = lockup_mint,
+        token::authority = base_vault,
+        bump
+    )]
+    pub base_vault: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
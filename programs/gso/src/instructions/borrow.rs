@@ -0,0 +1,65 @@
+use anchor_spl::token::{Token, TokenAccount};
+
+pub use crate::common::*;
+pub use crate::errors::ErrorCode;
+pub use crate::*;
+
+pub fn borrow(ctx: Context<GSOBorrow>, amount: u64) -> Result<()> {
+    msg!("GSO Borrow {}", amount);
+    let now_ts: u64 = Clock::get().unwrap().unix_timestamp as u64;
+    let expiration: u64 = ctx.accounts.gso_state.tier_lockup_period_end(0);
+    msg!("Now {} Expiration {}", now_ts, expiration);
+    invariant!(now_ts < expiration, BorrowAfterLockupEnd);
+
+    let outstanding_debt: u64 =
+        unwrap_int!(ctx.accounts.gso_state.outstanding_debt.checked_add(amount));
+    invariant!(
+        outstanding_debt <= ctx.accounts.gso_state.credit_limit,
+        CreditLimitExceeded
+    );
+    ctx.accounts.gso_state.outstanding_debt = outstanding_debt;
+
+    anchor_spl::token::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            anchor_spl::token::Transfer {
+                from: ctx.accounts.base_vault.to_account_info(),
+                to: ctx.accounts.destination.to_account_info(),
+                authority: ctx.accounts.base_vault.to_account_info(),
+            },
+            &[&[
+                BASE_VAULT_SEED,
+                &ctx.accounts.gso_state.key().to_bytes(),
+                &[ctx.accounts.gso_state.base_vault_bump],
+            ]],
+        ),
+        amount,
+    )?;
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(amount: u64)]
+pub struct GSOBorrow<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [GSO_STATE_SEED, &gso_state.period_num.to_be_bytes(), &gso_state.project_name.as_bytes()],
+        bump = gso_state.gso_state_bump,
+        constraint = gso_state.authority.key() == authority.key())]
+    pub gso_state: Box<Account<'info, GSOState>>,
+
+    #[account(mut,
+        seeds = [BASE_VAULT_SEED, &gso_state.key().to_bytes()],
+        bump = gso_state.base_vault_bump
+    )]
+    pub base_vault: Box<Account<'info, TokenAccount>>,
+
+    /// Where the borrowed tokens are going.
+    #[account(mut)]
+    pub destination: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
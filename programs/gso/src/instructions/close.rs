@@ -0,0 +1,129 @@
+use anchor_spl::token::{Mint, Token, TokenAccount};
+
+pub use crate::common::*;
+pub use crate::errors::ErrorCode;
+pub use crate::*;
+
+pub fn close<'info>(ctx: Context<'_, '_, '_, 'info, GSOClose<'info>>) -> Result<()> {
+    msg!("GSO Close");
+    let now_ts: u64 = Clock::get().unwrap().unix_timestamp as u64;
+    let expiration: u64 = ctx.accounts.gso_state.tier_lockup_period_end(0);
+    msg!("Now {} Expiration {}", now_ts, expiration);
+    invariant!(expiration < now_ts, NotYetExpired);
+
+    // Every xToken must have been burned through unstake, otherwise there are
+    // still holders with a claim on the vault.
+    invariant!(ctx.accounts.x_base_mint.supply == 0, XTokensOutstanding);
+    invariant!(ctx.accounts.base_vault.amount == 0, VaultNotEmpty);
+    // Withdraw signs for the SO state with the GSO state, so once that is
+    // closed whatever is left in the SO vault can never be recovered. SO
+    // withdraw closes its state, so either of these means it has run.
+    invariant!(
+        ctx.accounts.so_state.data_is_empty() || ctx.accounts.so_base_vault.amount == 0,
+        OptionsNotWithdrawn
+    );
+
+    msg!("Close base vault");
+    anchor_spl::token::close_account(CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        anchor_spl::token::CloseAccount {
+            account: ctx.accounts.base_vault.to_account_info(),
+            destination: ctx.accounts.authority.to_account_info(),
+            authority: ctx.accounts.base_vault.to_account_info(),
+        },
+        &[&[
+            BASE_VAULT_SEED,
+            &ctx.accounts.gso_state.key().to_bytes(),
+            &[ctx.accounts.gso_state.base_vault_bump],
+        ]],
+    ))?;
+
+    // Vaults for the additional lockup tiers are passed in remaining_accounts
+    // in tier order.
+    let gso_state_key: Pubkey = ctx.accounts.gso_state.key();
+    let tiers: Vec<LockupTier> = ctx.accounts.gso_state.tiers.clone();
+    invariant!(ctx.remaining_accounts.len() == tiers.len(), InvalidTier);
+    for (index, lockup_tier) in tiers.iter().enumerate() {
+        let tier: u8 = (index + 1) as u8;
+        msg!("Close tier {} base vault", tier);
+        invariant!(lockup_tier.lockup_period_end < now_ts, NotYetExpired);
+
+        let tier_base_vault_info: &AccountInfo<'info> = &ctx.remaining_accounts[index];
+        let tier_base_vault_seeds: &[&[u8]] = &[
+            BASE_VAULT_SEED,
+            &gso_state_key.to_bytes(),
+            tier_seed(&tier),
+            &[lockup_tier.base_vault_bump],
+        ];
+        let expected_tier_base_vault: Pubkey =
+            Pubkey::create_program_address(tier_base_vault_seeds, ctx.program_id)
+                .map_err(|_| error!(ErrorCode::InvalidTier))?;
+        invariant!(
+            tier_base_vault_info.key() == expected_tier_base_vault,
+            InvalidTier
+        );
+        let tier_base_vault: Account<TokenAccount> = Account::try_from(tier_base_vault_info)?;
+        invariant!(tier_base_vault.amount == 0, VaultNotEmpty);
+
+        anchor_spl::token::close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            anchor_spl::token::CloseAccount {
+                account: tier_base_vault_info.clone(),
+                destination: ctx.accounts.authority.to_account_info(),
+                authority: tier_base_vault_info.clone(),
+            },
+            &[tier_base_vault_seeds],
+        ))?;
+    }
+
+    // The x_base_mints are left in place. The SPL token program does not support
+    // closing mints, so their rent cannot be reclaimed.
+
+    // GSOState is closed by anchor in the account constraints.
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction()]
+pub struct GSOClose<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [GSO_STATE_SEED, &gso_state.period_num.to_be_bytes(), &gso_state.project_name.as_bytes()],
+        bump = gso_state.gso_state_bump,
+        constraint = gso_state.authority.key() == authority.key(),
+        close = authority
+    )]
+    pub gso_state: Box<Account<'info, GSOState>>,
+
+    #[account(
+        seeds = [X_GSO_SEED, &gso_state.key().to_bytes()],
+        bump = gso_state.x_base_mint_bump
+    )]
+    pub x_base_mint: Box<Account<'info, Mint>>,
+
+    #[account(mut,
+        seeds = [BASE_VAULT_SEED, &gso_state.key().to_bytes()],
+        bump = gso_state.base_vault_bump
+    )]
+    pub base_vault: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: Only the address is checked, it is closed by SO withdraw.
+    #[account(constraint = so_state.key() == gso_state.staking_options_state)]
+    pub so_state: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [
+            staking_options::SO_VAULT_SEED,
+            gso_state.staking_options_name().as_bytes(),
+            &gso_state.option_base_mint.to_bytes()
+        ],
+        bump,
+        seeds::program = staking_options::ID
+    )]
+    pub so_base_vault: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
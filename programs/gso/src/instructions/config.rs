@@ -5,6 +5,7 @@ pub use crate::common::*;
 pub use crate::errors::ErrorCode;
 pub use crate::*;
 
+#[allow(clippy::too_many_arguments)]
 pub fn config(
     ctx: Context<GSOConfig>,
     // GSO Params
@@ -21,13 +22,36 @@ pub fn config(
     strike: u64,
     // SO authority params
     so_authority_bump: u8,
+    // GSO options
+    options: ConfigOptions,
 ) -> Result<()> {
     msg!("GSO Config");
 
     invariant!(
-        lockup_period_end >= subscription_period_end,
-        InvalidLockupEnd
+        period_num == unwrap_int!(ctx.accounts.project.latest_period.checked_add(1)),
+        InvalidPeriodNum
     );
+    ctx.accounts.project.latest_period = period_num;
+
+    ConfigParams {
+        period_num,
+        lockup_ratio_tokens_per_million,
+        lockup_period_end,
+        option_expiration,
+        subscription_period_end,
+        lot_size,
+        num_tokens,
+        project_name: &project_name,
+        strike,
+        options: options.clone(),
+    }
+    .validate()?;
+    // The schedule runs from the subscription start, so default that to now.
+    let subscription_period_start: u64 = if options.subscription_period_start > 0 {
+        options.subscription_period_start
+    } else {
+        Clock::get().unwrap().unix_timestamp as u64
+    };
 
     msg!("SO Config");
     let so_config_accounts = staking_options::cpi::accounts::Config {
@@ -59,7 +83,7 @@ pub fn config(
         subscription_period_end,
         num_tokens,
         lot_size,
-        format!("{}{}", "GSO", project_name),
+        so_name(&project_name, period_num),
     )?;
 
     msg!("SO Init Strike");
@@ -90,8 +114,11 @@ pub fn config(
     msg!("GSO config params");
     // Store the bump for the GSO State and other values for the GSO wrapper.
     ctx.accounts.gso_state.period_num = period_num;
+    ctx.accounts.gso_state.so_name = so_name(&project_name, period_num);
     ctx.accounts.gso_state.strike = strike;
     ctx.accounts.gso_state.lockup_ratio_tokens_per_million = lockup_ratio_tokens_per_million;
+    ctx.accounts.gso_state.lockup_ratio_num = options.lockup_ratio_num;
+    ctx.accounts.gso_state.lockup_ratio_den = options.lockup_ratio_den;
     ctx.accounts.gso_state.gso_state_bump = *ctx.bumps.get("gso_state").unwrap();
     ctx.accounts.gso_state.x_base_mint_bump = *ctx.bumps.get("x_base_mint").unwrap();
     ctx.accounts.gso_state.so_authority_bump = so_authority_bump;
@@ -100,8 +127,38 @@ pub fn config(
     ctx.accounts.gso_state.project_name = project_name;
     ctx.accounts.gso_state.subscription_period_end = subscription_period_end;
     ctx.accounts.gso_state.authority = ctx.accounts.authority.key();
-    ctx.accounts.gso_state.base_mint = ctx.accounts.so_base_mint.key();
+    ctx.accounts.gso_state.option_base_mint = ctx.accounts.so_base_mint.key();
+    ctx.accounts.gso_state.lockup_mint = ctx.accounts.so_base_mint.key();
+    ctx.accounts.gso_state.lockup_decimals = ctx.accounts.so_base_mint.decimals;
+    ctx.accounts.gso_state.option_base_decimals = ctx.accounts.so_base_mint.decimals;
     ctx.accounts.gso_state.lockup_period_end = lockup_period_end;
+    ctx.accounts.gso_state.max_total_locked = options.max_total_locked;
+    ctx.accounts.gso_state.max_per_wallet = options.max_per_wallet;
+    ctx.accounts.gso_state.subscription_period_start = subscription_period_start;
+    ctx.accounts.gso_state.lockup_ratio_schedule = options.lockup_ratio_schedule;
+    ctx.accounts.gso_state.lockup_ratio_end_tokens_per_million =
+        options.lockup_ratio_end_tokens_per_million;
+    ctx.accounts.gso_state.lockup_ratio_steps = options.lockup_ratio_steps;
+    ctx.accounts.gso_state.max_slash_bps = options.max_slash_bps;
+    ctx.accounts.gso_state.slash_timelock = options.slash_timelock;
+    ctx.accounts.gso_state.slash_destination = options.slash_destination;
+    ctx.accounts.gso_state.credit_limit = options.credit_limit;
+    ctx.accounts.gso_state.vesting_end = options.vesting_end;
+    ctx.accounts.gso_state.early_unstake_penalty_bps = options.early_unstake_penalty_bps;
+    ctx.accounts.gso_state.early_unstake_penalty_decay = options.early_unstake_penalty_decay;
+    ctx.accounts.gso_state.early_unstake_penalty_destination =
+        options.early_unstake_penalty_destination;
+
+    emit!(Configured {
+        gso_state: ctx.accounts.gso_state.key(),
+        authority: ctx.accounts.authority.key(),
+        project_name: ctx.accounts.gso_state.project_name.clone(),
+        period_num,
+        staking_options_state: ctx.accounts.so_state.key(),
+        subscription_period_end,
+        lockup_period_end,
+        timestamp: Clock::get().unwrap().unix_timestamp,
+    });
 
     Ok(())
 }
@@ -135,6 +192,13 @@ pub struct GSOConfig<'info> {
     )]
     pub gso_state: Box<Account<'info, GSOState>>,
 
+    #[account(
+        mut,
+        seeds = [PROJECT_SEED, project_name.as_bytes()],
+        bump = project.bump,
+        constraint = project.authority.key() == authority.key())]
+    pub project: Box<Account<'info, Project>>,
+
     /// SO Config
     /// =========
     /// CHECK: Not dangerous. This is just a PDA, not a funded account.
@@ -0,0 +1,190 @@
+use anchor_spl::token::{Mint, Token, TokenAccount};
+
+pub use crate::common::*;
+pub use crate::errors::ErrorCode;
+pub use crate::*;
+
+pub fn early_unstake(ctx: Context<GSOEarlyUnstake>, amount: u64, tier: u8) -> Result<()> {
+    msg!("GSO Early Unstake");
+    invariant!(ctx.accounts.gso_state.paused & PAUSE_UNSTAKE == 0, Paused);
+
+    // Once the options expire they are worthless, so returning them would
+    // make early unstaking free.
+    let now_ts: u64 = Clock::get().unwrap().unix_timestamp as u64;
+    invariant!(
+        now_ts <= ctx.accounts.so_state.option_expiration,
+        OptionsExpired
+    );
+    // After the lockup, unstake applies vesting and is the only way out. Like
+    // the early path of unstake, exits before the lockup ends are not counted
+    // in total_unstaked, since they are no longer part of what vests.
+    invariant!(
+        now_ts <= ctx.accounts.gso_state.tier_lockup_period_end(tier),
+        LockupEnded
+    );
+
+    // xTokens are fungible, so it is not known what strike or ratio they were
+    // staked at. Price the exit at the strike that earns the most options,
+    // using the ratio from the start of the subscription period, which is the
+    // highest, and round up so exiting is never cheaper than staking paid.
+    let strike_index: u8 = ctx
+        .accounts
+        .gso_state
+        .highest_ratio_strike_index(ctx.accounts.gso_state.subscription_period_start, tier)?;
+
+    // The option mint is checked here since there is no CPI into SO to do it.
+    let strike: u64 = ctx.accounts.gso_state.strike_at(strike_index);
+    let (expected_option_mint, _option_mint_bump) = Pubkey::find_program_address(
+        &[
+            staking_options::SO_MINT_SEED,
+            &ctx.accounts.so_state.key().to_bytes(),
+            &strike.to_be_bytes(),
+        ],
+        &staking_options::ID,
+    );
+    invariant!(
+        ctx.accounts.so_option_mint.key() == expected_option_mint,
+        InvalidStrikeIndex
+    );
+
+    let lockup_ratio_tokens_per_million: u64 = ctx.accounts.gso_state.effective_lockup_ratio(
+        ctx.accounts.gso_state.subscription_period_start,
+        tier,
+        strike_index,
+    )?;
+    let num_staking_options: u64 = ctx.accounts.gso_state.options_for_lockup(
+        amount,
+        lockup_ratio_tokens_per_million,
+        math::Rounding::Up,
+    )?;
+    let num_option_tokens: u64 =
+        math::mul_div_ceil(num_staking_options, 1, ctx.accounts.so_state.lot_size)?;
+    msg!(
+        "Returning {} options for {} xTokens",
+        num_option_tokens,
+        amount
+    );
+
+    // Tier 0 can not be unstaked while borrowed tokens are missing from the
+    // vault, otherwise stakers would silently get less than 1:1.
+    if tier == 0 {
+        invariant!(
+            ctx.accounts.gso_state.outstanding_debt == 0,
+            DebtOutstanding
+        );
+    }
+
+    // Saturating since states from before total_locked was tracked start at 0.
+    ctx.accounts.gso_state.total_locked =
+        ctx.accounts.gso_state.total_locked.saturating_sub(amount);
+
+    // Pro-rata share of the vault. This is 1:1 unless the vault was slashed.
+    let return_amount: u64 = math::mul_div_floor(
+        amount,
+        ctx.accounts.base_vault.amount,
+        ctx.accounts.x_base_mint.supply,
+    )?;
+    msg!("Returning {} for {} xTokens", return_amount, amount);
+
+    // The tokens backing these options stay in the SO vault and go back to
+    // the project on withdraw.
+    msg!("Burn options");
+    anchor_spl::token::burn(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            anchor_spl::token::Burn {
+                mint: ctx.accounts.so_option_mint.to_account_info(),
+                from: ctx.accounts.so_user_option_account.to_account_info(),
+                authority: ctx.accounts.authority.to_account_info(),
+            },
+        ),
+        num_option_tokens,
+    )?;
+
+    msg!("Burn xTokens");
+    anchor_spl::token::burn(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            anchor_spl::token::Burn {
+                mint: ctx.accounts.x_base_mint.to_account_info(),
+                from: ctx.accounts.user_x_base_account.to_account_info(),
+                authority: ctx.accounts.authority.to_account_info(),
+            },
+        ),
+        amount,
+    )?;
+
+    msg!("Return tokens");
+    anchor_spl::token::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            anchor_spl::token::Transfer {
+                from: ctx.accounts.base_vault.to_account_info(),
+                to: ctx.accounts.user_base_account.to_account_info(),
+                authority: ctx.accounts.base_vault.to_account_info(),
+            },
+            &[&[
+                BASE_VAULT_SEED,
+                &ctx.accounts.gso_state.key().to_bytes(),
+                tier_seed(&tier),
+                &[ctx.accounts.gso_state.tier_base_vault_bump(tier)],
+            ]],
+        ),
+        return_amount,
+    )?;
+
+    emit!(Unstaked {
+        gso_state: ctx.accounts.gso_state.key(),
+        user: ctx.accounts.authority.key(),
+        tier,
+        amount,
+        amount_returned: return_amount,
+        penalty: 0,
+        options_burned: num_option_tokens,
+        timestamp: now_ts as i64,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(amount: u64, tier: u8)]
+pub struct GSOEarlyUnstake<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [GSO_STATE_SEED, &gso_state.period_num.to_be_bytes(), &gso_state.project_name.as_bytes()],
+        bump = gso_state.gso_state_bump,
+        constraint = gso_state.is_valid_tier(tier) @ ErrorCode::InvalidTier,
+    )]
+    pub gso_state: Box<Account<'info, GSOState>>,
+
+    #[account(constraint = so_state.key() == gso_state.staking_options_state)]
+    pub so_state: Box<Account<'info, staking_options::State>>,
+
+    /// Verified against the strike with the highest ratio.
+    #[account(mut)]
+    pub so_option_mint: Box<Account<'info, Mint>>,
+    #[account(mut)]
+    pub so_user_option_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [X_GSO_SEED, &gso_state.key().to_bytes(), tier_seed(&tier)],
+        bump = gso_state.tier_x_base_mint_bump(tier)
+    )]
+    pub x_base_mint: Box<Account<'info, Mint>>,
+    #[account(mut, constraint = user_base_account.mint == base_vault.mint @ ErrorCode::InvalidTokenMint)]
+    pub user_base_account: Box<Account<'info, TokenAccount>>,
+    #[account(mut, constraint = user_x_base_account.mint == x_base_mint.key() @ ErrorCode::InvalidTokenMint)]
+    pub user_x_base_account: Box<Account<'info, TokenAccount>>,
+    #[account(mut,
+        seeds = [BASE_VAULT_SEED, &gso_state.key().to_bytes(), tier_seed(&tier)],
+        bump = gso_state.tier_base_vault_bump(tier)
+    )]
+    pub base_vault: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
@@ -0,0 +1,84 @@
+use anchor_spl::token::{Token, TokenAccount};
+
+pub use crate::common::*;
+pub use crate::errors::ErrorCode;
+pub use crate::*;
+
+pub fn execute_slash(ctx: Context<GSOExecuteSlash>, tier: u8) -> Result<()> {
+    msg!("GSO Execute Slash");
+    let amount: u64 = ctx.accounts.gso_state.pending_slash_amount;
+    invariant!(
+        amount > 0 && ctx.accounts.gso_state.pending_slash_tier == tier,
+        NoPendingSlash
+    );
+    let now_ts: u64 = Clock::get().unwrap().unix_timestamp as u64;
+    let executable_ts: u64 = ctx.accounts.gso_state.pending_slash_executable_ts;
+    msg!("Now {} Executable {}", now_ts, executable_ts);
+    invariant!(now_ts >= executable_ts, SlashTimelocked);
+    // Only for slashes proposed before this was checked in propose_slash.
+    invariant!(
+        now_ts > ctx.accounts.gso_state.subscription_period_end,
+        SlashDuringSubscription
+    );
+
+    let total_slashed: u64 = ctx.accounts.gso_state.tier_total_slashed(tier);
+    let allowance: u64 = math::remaining_slash_allowance(
+        ctx.accounts.base_vault.amount,
+        total_slashed,
+        ctx.accounts.gso_state.max_slash_bps,
+    )?;
+    invariant!(amount <= allowance, SlashTooLarge);
+
+    ctx.accounts
+        .gso_state
+        .set_tier_total_slashed(tier, unwrap_int!(total_slashed.checked_add(amount)));
+    ctx.accounts.gso_state.pending_slash_amount = 0;
+
+    msg!("Slash {} from tier {}", amount, tier);
+    anchor_spl::token::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            anchor_spl::token::Transfer {
+                from: ctx.accounts.base_vault.to_account_info(),
+                to: ctx.accounts.slash_destination.to_account_info(),
+                authority: ctx.accounts.base_vault.to_account_info(),
+            },
+            &[&[
+                BASE_VAULT_SEED,
+                &ctx.accounts.gso_state.key().to_bytes(),
+                tier_seed(&tier),
+                &[ctx.accounts.gso_state.tier_base_vault_bump(tier)],
+            ]],
+        ),
+        amount,
+    )?;
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(tier: u8)]
+pub struct GSOExecuteSlash<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [GSO_STATE_SEED, &gso_state.period_num.to_be_bytes(), &gso_state.project_name.as_bytes()],
+        bump = gso_state.gso_state_bump,
+        constraint = gso_state.authority.key() == authority.key(),
+        constraint = gso_state.is_valid_tier(tier) @ ErrorCode::InvalidTier,
+    )]
+    pub gso_state: Box<Account<'info, GSOState>>,
+
+    #[account(mut,
+        seeds = [BASE_VAULT_SEED, &gso_state.key().to_bytes(), tier_seed(&tier)],
+        bump = gso_state.tier_base_vault_bump(tier)
+    )]
+    pub base_vault: Box<Account<'info, TokenAccount>>,
+
+    /// Where the slashed tokens are going. Fixed at config.
+    #[account(mut, constraint = slash_destination.key() == gso_state.slash_destination)]
+    pub slash_destination: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
@@ -0,0 +1,88 @@
+pub use crate::common::*;
+pub use crate::*;
+
+pub fn init_project(
+    ctx: Context<GSOInitProject>,
+    project_name: String,
+    latest_period: u64,
+    default_lockup_ratio_tokens_per_million: u64,
+    default_lot_size: u64,
+    default_strike: u64,
+) -> Result<()> {
+    msg!("GSO Init Project {}", project_name);
+    validate_project_name(&project_name)?;
+
+    // GSOs from before projects existed may already use the name. Only the
+    // authority of the latest one can claim it, and latest_period carries
+    // their numbering on so the next config does not collide with them.
+    invariant!(
+        ctx.accounts.next_gso_state.data_is_empty(),
+        InvalidPeriodNum
+    );
+    let claimed = ctx.accounts.project.authority != Pubkey::default();
+    if ctx.accounts.latest_gso_state.data_is_empty() {
+        invariant!(latest_period == 0 && !claimed, InvalidPeriodNum);
+    } else {
+        let latest_gso_state: Account<GSOState> =
+            Account::try_from(&ctx.accounts.latest_gso_state.to_account_info())?;
+        invariant!(
+            latest_gso_state.authority == ctx.accounts.authority.key(),
+            ProjectNameTaken
+        );
+        // A project without that check could have been claimed for a name
+        // whose GSOs do not start at period 0 or 1. Their authority can take
+        // it back with a GSO from before projects, which are the only ones
+        // without a stored SO name, from after the project's latest period.
+        if claimed {
+            invariant!(
+                latest_gso_state.so_name.is_empty()
+                    && latest_period > ctx.accounts.project.latest_period,
+                ProjectNameTaken
+            );
+        }
+    }
+
+    ctx.accounts.project.authority = ctx.accounts.authority.key();
+    ctx.accounts.project.project_name = project_name;
+    ctx.accounts.project.latest_period = latest_period;
+    ctx.accounts.project.default_lockup_ratio_tokens_per_million =
+        default_lockup_ratio_tokens_per_million;
+    ctx.accounts.project.default_lot_size = default_lot_size;
+    ctx.accounts.project.default_strike = default_strike;
+    ctx.accounts.project.bump = *ctx.bumps.get("project").unwrap();
+    ctx.accounts.project.pending_authority = Pubkey::default();
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(project_name: String, latest_period: u64)]
+pub struct GSOInitProject<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        seeds = [PROJECT_SEED, project_name.as_bytes()],
+        bump,
+        space = 8 + 32 + 4 + 32 + 8 + 8 + 8 + 8 + 1 + 32 + 64 // Padding
+    )]
+    pub project: Box<Account<'info, Project>>,
+
+    /// CHECK: GSOState for latest_period, which may not exist.
+    #[account(
+        seeds = [GSO_STATE_SEED, &latest_period.to_be_bytes(), project_name.as_bytes()],
+        bump
+    )]
+    pub latest_gso_state: UncheckedAccount<'info>,
+
+    /// CHECK: GSOState for the period after latest_period, which must not exist.
+    #[account(
+        seeds = [GSO_STATE_SEED, &latest_period.saturating_add(1).to_be_bytes(), project_name.as_bytes()],
+        bump
+    )]
+    pub next_gso_state: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
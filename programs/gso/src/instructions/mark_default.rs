@@ -0,0 +1,29 @@
+pub use crate::common::*;
+pub use crate::errors::ErrorCode;
+pub use crate::*;
+
+pub fn mark_default(ctx: Context<GSOMarkDefault>) -> Result<()> {
+    msg!("GSO Mark Default");
+    let now_ts: u64 = Clock::get().unwrap().unix_timestamp as u64;
+    let expiration: u64 = ctx.accounts.gso_state.tier_lockup_period_end(0);
+    msg!("Now {} Expiration {}", now_ts, expiration);
+    invariant!(
+        expiration < now_ts && ctx.accounts.gso_state.outstanding_debt > 0,
+        NotInDefault
+    );
+
+    ctx.accounts.gso_state.defaulted = true;
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction()]
+pub struct GSOMarkDefault<'info> {
+    #[account(
+        mut,
+        seeds = [GSO_STATE_SEED, &gso_state.period_num.to_be_bytes(), &gso_state.project_name.as_bytes()],
+        bump = gso_state.gso_state_bump,
+    )]
+    pub gso_state: Box<Account<'info, GSOState>>,
+}
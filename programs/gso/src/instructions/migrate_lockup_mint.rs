@@ -0,0 +1,36 @@
+use anchor_spl::token::TokenAccount;
+
+pub use crate::common::*;
+pub use crate::errors::ErrorCode;
+pub use crate::*;
+
+pub fn migrate_lockup_mint(ctx: Context<GSOMigrateLockupMint>) -> Result<()> {
+    msg!("GSO Migrate Lockup Mint");
+    invariant!(
+        ctx.accounts.gso_state.lockup_mint == Pubkey::default(),
+        AlreadyMigrated
+    );
+
+    // The tier 0 vault was created for the lockup mint by either config path,
+    // so it is the record of what v2 states actually lock.
+    ctx.accounts.gso_state.lockup_mint = ctx.accounts.base_vault.mint;
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction()]
+pub struct GSOMigrateLockupMint<'info> {
+    #[account(
+        mut,
+        seeds = [GSO_STATE_SEED, &gso_state.period_num.to_be_bytes(), &gso_state.project_name.as_bytes()],
+        bump = gso_state.gso_state_bump,
+    )]
+    pub gso_state: Box<Account<'info, GSOState>>,
+
+    #[account(
+        seeds = [BASE_VAULT_SEED, &gso_state.key().to_bytes()],
+        bump = gso_state.base_vault_bump
+    )]
+    pub base_vault: Box<Account<'info, TokenAccount>>,
+}
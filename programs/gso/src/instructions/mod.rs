@@ -1,13 +1,49 @@
+pub mod accept_authority;
+pub mod accept_project_authority;
+pub mod add_strike;
+pub mod add_tier;
+pub mod borrow;
+pub mod close;
 pub mod config;
 pub mod config_v2;
+pub mod early_unstake;
+pub mod execute_slash;
+pub mod init_project;
+pub mod mark_default;
+pub mod migrate_lockup_mint;
 pub mod name_tokens;
+pub mod propose_authority;
+pub mod propose_project_authority;
+pub mod propose_slash;
+pub mod repay;
+pub mod rollover;
+pub mod set_pause;
+pub mod set_project_defaults;
 pub mod stake;
 pub mod unstake;
 pub mod withdraw;
 
+pub use accept_authority::*;
+pub use accept_project_authority::*;
+pub use add_strike::*;
+pub use add_tier::*;
+pub use borrow::*;
+pub use close::*;
 pub use config::*;
 pub use config_v2::*;
+pub use early_unstake::*;
+pub use execute_slash::*;
+pub use init_project::*;
+pub use mark_default::*;
+pub use migrate_lockup_mint::*;
 pub use name_tokens::*;
+pub use propose_authority::*;
+pub use propose_project_authority::*;
+pub use propose_slash::*;
+pub use repay::*;
+pub use rollover::*;
+pub use set_pause::*;
+pub use set_project_defaults::*;
 pub use stake::*;
 pub use unstake::*;
 pub use withdraw::*;
@@ -3,24 +3,112 @@ use anchor_spl::token::Mint;
 use staking_options::program::StakingOptions as StakingOptionsProgram;
 
 pub use crate::common::*;
+pub use crate::errors::ErrorCode;
+use crate::events::*;
+use vipers::prelude::*;
+
+pub fn name_tokens<'info>(ctx: Context<'_, '_, '_, 'info, GSONameTokens<'info>>) -> Result<()> {
+    // Metadata that already exists is skipped, so this can be run again to
+    // name the strikes and tiers added since the last time.
+    if ctx.accounts.option_metadata.data_is_empty() {
+        msg!("SO Name Tokens");
+        name_option(
+            &ctx,
+            ctx.accounts.so_option_mint.to_account_info(),
+            ctx.accounts.option_metadata.to_account_info(),
+            ctx.accounts.so_state.strikes[0],
+        )?;
+    }
+
+    // Additional strikes are passed in remaining_accounts as pairs of option
+    // mint and option metadata, in strike index order, followed by pairs of
+    // xBase mint and metadata for the additional tiers in tier order. The
+    // option mint is verified against the strike in the CPI.
+    let num_strikes: usize = ctx.accounts.gso_state.strikes.len();
+    invariant!(
+        ctx.remaining_accounts.len() == 2 * (num_strikes + ctx.accounts.gso_state.tiers.len()),
+        InvalidStrikeIndex
+    );
+    for (index, strike_level) in ctx.accounts.gso_state.strikes.iter().enumerate() {
+        if !ctx.remaining_accounts[2 * index + 1].data_is_empty() {
+            continue;
+        }
+        msg!("SO Name Token for strike {}", strike_level.strike);
+        name_option(
+            &ctx,
+            ctx.remaining_accounts[2 * index].clone(),
+            ctx.remaining_accounts[2 * index + 1].clone(),
+            strike_level.strike,
+        )?;
+    }
+
+    if ctx.accounts.x_base_metadata.data_is_empty() {
+        msg!("GSO Name Token for collateral");
+        name_x_base(
+            &ctx,
+            ctx.accounts.x_base_mint.to_account_info(),
+            ctx.accounts.x_base_metadata.to_account_info(),
+            0,
+            ctx.accounts.gso_state.x_base_mint_bump,
+        )?;
+    }
+
+    let gso_state_key: Pubkey = ctx.accounts.gso_state.key();
+    for (index, lockup_tier) in ctx.accounts.gso_state.tiers.iter().enumerate() {
+        let tier: u8 = (index + 1) as u8;
+        let offset: usize = 2 * (num_strikes + index);
+        let x_base_mint_info: AccountInfo<'info> = ctx.remaining_accounts[offset].clone();
+        let expected_x_base_mint: Pubkey = Pubkey::create_program_address(
+            &[
+                X_GSO_SEED,
+                &gso_state_key.to_bytes(),
+                tier_seed(&tier),
+                &[lockup_tier.x_base_mint_bump],
+            ],
+            ctx.program_id,
+        )
+        .map_err(|_| error!(ErrorCode::InvalidTier))?;
+        invariant!(x_base_mint_info.key() == expected_x_base_mint, InvalidTier);
+        if !ctx.remaining_accounts[offset + 1].data_is_empty() {
+            continue;
+        }
+        msg!("GSO Name Token for tier {} collateral", tier);
+        name_x_base(
+            &ctx,
+            x_base_mint_info,
+            ctx.remaining_accounts[offset + 1].clone(),
+            tier,
+            lockup_tier.x_base_mint_bump,
+        )?;
+    }
+
+    emit!(TokensNamed {
+        gso_state: ctx.accounts.gso_state.key(),
+        timestamp: Clock::get().unwrap().unix_timestamp,
+    });
+
+    Ok(())
+}
 
-pub fn name_tokens(ctx: Context<GSONameTokens>) -> Result<()> {
-    msg!("SO Name Tokens");
+fn name_option<'info>(
+    ctx: &Context<'_, '_, '_, 'info, GSONameTokens<'info>>,
+    option_mint: AccountInfo<'info>,
+    option_metadata: AccountInfo<'info>,
+    strike: u64,
+) -> Result<()> {
     let so_name_token_accounts = staking_options::cpi::accounts::NameToken {
         authority: ctx.accounts.so_authority.to_account_info(),
         payer: ctx.accounts.authority.to_account_info(),
         state: ctx.accounts.so_state.to_account_info(),
-        option_mint: ctx.accounts.so_option_mint.to_account_info(),
-        option_mint_metadata_account: ctx.accounts.option_metadata.to_account_info(),
+        option_mint,
+        option_mint_metadata_account: option_metadata,
         token_metadata_program: ctx.accounts.token_metadata_program.to_account_info(),
         system_program: ctx.accounts.system_program.to_account_info(),
         rent: ctx.accounts.rent.to_account_info(),
     };
-    let cpi_program_name = ctx.accounts.staking_options_program.to_account_info();
-
     staking_options::cpi::name_token(
         CpiContext::new_with_signer(
-            cpi_program_name,
+            ctx.accounts.staking_options_program.to_account_info(),
             so_name_token_accounts,
             &[&[
                 SO_AUTHORITY_SEED,
@@ -28,19 +116,34 @@ pub fn name_tokens(ctx: Context<GSONameTokens>) -> Result<()> {
                 &[ctx.accounts.gso_state.so_authority_bump],
             ]],
         ),
-        ctx.accounts.so_state.strikes[0],
-    )?;
+        strike,
+    )
+}
 
-    let token_name: String = format!("DUAL-GSO-{:.15}", ctx.accounts.gso_state.project_name);
+fn name_x_base<'info>(
+    ctx: &Context<'_, '_, '_, 'info, GSONameTokens<'info>>,
+    x_base_mint: AccountInfo<'info>,
+    x_base_metadata: AccountInfo<'info>,
+    tier: u8,
+    x_base_mint_bump: u8,
+) -> Result<()> {
+    // Tier 0 keeps the name it always had.
+    let token_name: String = if tier == 0 {
+        format!("DUAL-GSO-{:.15}", ctx.accounts.gso_state.project_name)
+    } else {
+        format!(
+            "DUAL-GSO-{:.15}-T{}",
+            ctx.accounts.gso_state.project_name, tier
+        )
+    };
     let symbol: String = format!("DUAL-GSO");
-    msg!("GSO Name Token for collateral");
     let ix = mpl_token_metadata::instruction::create_metadata_accounts_v3(
         mpl_token_metadata::ID,
-        *ctx.accounts.x_base_metadata.key,
-        ctx.accounts.x_base_mint.key(),
-        ctx.accounts.x_base_mint.key(),
+        x_base_metadata.key(),
+        x_base_mint.key(),
+        x_base_mint.key(),
         ctx.accounts.authority.key(),
-        ctx.accounts.x_base_mint.key(),
+        x_base_mint.key(),
         token_name,
         symbol,
         "https://www.dual.finance/images/token-logos/gso-collateral".to_string(),
@@ -56,21 +159,21 @@ pub fn name_tokens(ctx: Context<GSONameTokens>) -> Result<()> {
     solana_program::program::invoke_signed(
         &ix,
         &[
-            ctx.accounts.x_base_metadata.to_account_info(),
-            ctx.accounts.x_base_mint.to_account_info(),
-            ctx.accounts.x_base_mint.to_account_info(),
+            x_base_metadata,
+            x_base_mint.clone(),
+            x_base_mint.clone(),
             ctx.accounts.authority.to_account_info(),
-            ctx.accounts.x_base_mint.to_account_info(),
+            x_base_mint,
             ctx.accounts.system_program.to_account_info(),
             ctx.accounts.rent.to_account_info(),
         ],
         &[&[
             X_GSO_SEED,
             &ctx.accounts.gso_state.key().to_bytes(),
-            &[ctx.accounts.gso_state.x_base_mint_bump],
+            tier_seed(&tier),
+            &[x_base_mint_bump],
         ]],
     )?;
-
     Ok(())
 }
 
@@ -0,0 +1,28 @@
+pub use crate::common::*;
+pub use crate::*;
+
+pub fn propose_authority(
+    ctx: Context<GSOProposeAuthority>,
+    pending_authority: Pubkey,
+) -> Result<()> {
+    msg!("GSO Propose Authority {}", pending_authority);
+
+    // The authority does not change until the pending authority accepts, so a
+    // wrong key here can just be overwritten with another proposal.
+    ctx.accounts.gso_state.pending_authority = pending_authority;
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(pending_authority: Pubkey)]
+pub struct GSOProposeAuthority<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [GSO_STATE_SEED, &gso_state.period_num.to_be_bytes(), &gso_state.project_name.as_bytes()],
+        bump = gso_state.gso_state_bump,
+        constraint = gso_state.authority.key() == authority.key())]
+    pub gso_state: Box<Account<'info, GSOState>>,
+}
@@ -0,0 +1,28 @@
+pub use crate::common::*;
+pub use crate::*;
+
+pub fn propose_project_authority(
+    ctx: Context<GSOProposeProjectAuthority>,
+    pending_authority: Pubkey,
+) -> Result<()> {
+    msg!("GSO Propose Project Authority {}", pending_authority);
+
+    // The authority does not change until the pending authority accepts, so a
+    // wrong key here can just be overwritten with another proposal.
+    ctx.accounts.project.pending_authority = pending_authority;
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(pending_authority: Pubkey)]
+pub struct GSOProposeProjectAuthority<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [PROJECT_SEED, project.project_name.as_bytes()],
+        bump = project.bump,
+        constraint = project.authority.key() == authority.key())]
+    pub project: Box<Account<'info, Project>>,
+}
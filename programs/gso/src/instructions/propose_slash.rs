@@ -0,0 +1,58 @@
+use anchor_spl::token::TokenAccount;
+
+pub use crate::common::*;
+pub use crate::errors::ErrorCode;
+pub use crate::*;
+
+pub fn propose_slash(ctx: Context<GSOProposeSlash>, tier: u8, amount: u64) -> Result<()> {
+    msg!("GSO Propose Slash {} from tier {}", amount, tier);
+    invariant!(ctx.accounts.gso_state.max_slash_bps > 0, SlashingDisabled);
+
+    // xTokens are minted 1:1 while the subscription is open, so a slash before
+    // then would take part of every later deposit. The subscription end is the
+    // same for every tier, including ones added later.
+    let now_ts: u64 = Clock::get().unwrap().unix_timestamp as u64;
+    invariant!(
+        now_ts > ctx.accounts.gso_state.subscription_period_end,
+        SlashDuringSubscription
+    );
+
+    // Checked again on execution since the vault can change in between.
+    let allowance: u64 = math::remaining_slash_allowance(
+        ctx.accounts.base_vault.amount,
+        ctx.accounts.gso_state.tier_total_slashed(tier),
+        ctx.accounts.gso_state.max_slash_bps,
+    )?;
+    invariant!(amount <= allowance, SlashTooLarge);
+
+    // A new proposal replaces any pending one and restarts the timelock. An
+    // amount of 0 cancels the pending slash.
+    let executable_ts: u64 = unwrap_int!(now_ts.checked_add(ctx.accounts.gso_state.slash_timelock));
+    msg!("Executable at {}", executable_ts);
+    ctx.accounts.gso_state.pending_slash_amount = amount;
+    ctx.accounts.gso_state.pending_slash_tier = tier;
+    ctx.accounts.gso_state.pending_slash_executable_ts = executable_ts;
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(tier: u8)]
+pub struct GSOProposeSlash<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [GSO_STATE_SEED, &gso_state.period_num.to_be_bytes(), &gso_state.project_name.as_bytes()],
+        bump = gso_state.gso_state_bump,
+        constraint = gso_state.authority.key() == authority.key(),
+        constraint = gso_state.is_valid_tier(tier) @ ErrorCode::InvalidTier,
+    )]
+    pub gso_state: Box<Account<'info, GSOState>>,
+
+    #[account(
+        seeds = [BASE_VAULT_SEED, &gso_state.key().to_bytes(), tier_seed(&tier)],
+        bump = gso_state.tier_base_vault_bump(tier)
+    )]
+    pub base_vault: Box<Account<'info, TokenAccount>>,
+}
@@ -0,0 +1,56 @@
+use anchor_spl::token::{Token, TokenAccount};
+
+pub use crate::common::*;
+pub use crate::*;
+
+pub fn repay(ctx: Context<GSORepay>, amount: u64) -> Result<()> {
+    // Anyone can repay on behalf of the project. Anything above the
+    // outstanding debt is not taken.
+    let repay_amount: u64 = amount.min(ctx.accounts.gso_state.outstanding_debt);
+    msg!("GSO Repay {}", repay_amount);
+
+    ctx.accounts.gso_state.outstanding_debt = unwrap_int!(ctx
+        .accounts
+        .gso_state
+        .outstanding_debt
+        .checked_sub(repay_amount));
+
+    anchor_spl::token::transfer(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            anchor_spl::token::Transfer {
+                from: ctx.accounts.source.to_account_info(),
+                to: ctx.accounts.base_vault.to_account_info(),
+                authority: ctx.accounts.authority.to_account_info(),
+            },
+        ),
+        repay_amount,
+    )?;
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(amount: u64)]
+pub struct GSORepay<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [GSO_STATE_SEED, &gso_state.period_num.to_be_bytes(), &gso_state.project_name.as_bytes()],
+        bump = gso_state.gso_state_bump,
+    )]
+    pub gso_state: Box<Account<'info, GSOState>>,
+
+    #[account(mut,
+        seeds = [BASE_VAULT_SEED, &gso_state.key().to_bytes()],
+        bump = gso_state.base_vault_bump
+    )]
+    pub base_vault: Box<Account<'info, TokenAccount>>,
+
+    /// Where the repaid tokens are coming from.
+    #[account(mut)]
+    pub source: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
@@ -0,0 +1,357 @@
+use anchor_spl::token::{Mint, Token, TokenAccount};
+use staking_options::program::StakingOptions as StakingOptionsProgram;
+
+pub use crate::common::*;
+pub use crate::errors::ErrorCode;
+pub use crate::*;
+
+pub fn rollover(
+    ctx: Context<GSORollover>,
+    amount: u64,
+    old_tier: u8,
+    tier: u8,
+    strike_index: u8,
+) -> Result<()> {
+    msg!("GSO Rollover");
+    invariant!(
+        ctx.accounts.old_gso_state.paused & PAUSE_UNSTAKE == 0,
+        Paused
+    );
+    invariant!(ctx.accounts.gso_state.paused & PAUSE_STAKE == 0, Paused);
+
+    // Both periods have the project name in their seeds, so matching names
+    // means the same project.
+    invariant!(
+        ctx.accounts.old_gso_state.project_name == ctx.accounts.gso_state.project_name,
+        RolloverMismatch
+    );
+    invariant!(
+        ctx.accounts.old_gso_state.period_num < ctx.accounts.gso_state.period_num,
+        RolloverMismatch
+    );
+    invariant!(
+        ctx.accounts.old_base_vault.mint == ctx.accounts.base_vault.mint,
+        RolloverMismatch
+    );
+
+    // Unstake side. Same checks as unstake after the lockup, there is no early
+    // rollover.
+    let now_ts: u64 = Clock::get().unwrap().unix_timestamp as u64;
+    let expiration: u64 = ctx.accounts.old_gso_state.tier_lockup_period_end(old_tier);
+    msg!("Now {} Expiration {}", now_ts, expiration);
+    invariant!(expiration < now_ts, NotYetExpired);
+
+    let total_unstaked: u64 = ctx.accounts.old_gso_state.tier_total_unstaked(old_tier);
+    let vesting_end: u64 = ctx.accounts.old_gso_state.tier_vesting_end(old_tier)?;
+    if vesting_end > now_ts {
+        let total_staked: u64 = unwrap_int!(ctx
+            .accounts
+            .old_x_base_mint
+            .supply
+            .checked_add(total_unstaked));
+        let vested: u64 = math::vested_amount(total_staked, expiration, vesting_end, now_ts)?;
+        msg!("Vested {} Unstaked {}", vested, total_unstaked);
+        invariant!(
+            unwrap_int!(total_unstaked.checked_add(amount)) <= vested,
+            NotYetVested
+        );
+    }
+    ctx.accounts
+        .old_gso_state
+        .set_tier_total_unstaked(old_tier, unwrap_int!(total_unstaked.checked_add(amount)));
+
+    if old_tier == 0 {
+        invariant!(
+            ctx.accounts.old_gso_state.outstanding_debt == 0,
+            DebtOutstanding
+        );
+    }
+    ctx.accounts.old_gso_state.total_locked = ctx
+        .accounts
+        .old_gso_state
+        .total_locked
+        .saturating_sub(amount);
+
+    let return_amount: u64 = math::mul_div_floor(
+        amount,
+        ctx.accounts.old_base_vault.amount,
+        ctx.accounts.old_x_base_mint.supply,
+    )?;
+
+    // Stake side. Same checks as stake for the new period.
+    invariant!(
+        now_ts <= ctx.accounts.gso_state.subscription_period_end,
+        SubscriptionEnded
+    );
+    invariant!(
+        now_ts >= ctx.accounts.gso_state.subscription_period_start,
+        SubscriptionNotStarted
+    );
+    let lockup_ratio_tokens_per_million: u64 =
+        ctx.accounts
+            .gso_state
+            .effective_lockup_ratio(now_ts, tier, strike_index)?;
+    let lockup_ratio: math::Ratio = ctx
+        .accounts
+        .gso_state
+        .lockup_ratio(lockup_ratio_tokens_per_million)?;
+    msg!(
+        "Effective lockup ratio {}/{}",
+        lockup_ratio.numerator,
+        lockup_ratio.denominator
+    );
+    let num_staking_options: u64 = ctx.accounts.gso_state.options_for_lockup(
+        return_amount,
+        lockup_ratio_tokens_per_million,
+        math::Rounding::Down,
+    )?;
+    let num_staking_options: u64 =
+        math::round_down_to_lot(num_staking_options, ctx.accounts.so_state.lot_size)?;
+    invariant!(num_staking_options > 0, StakeTooSmall);
+    let lockup_amount: u64 = ctx
+        .accounts
+        .gso_state
+        .lockup_for_options(num_staking_options, lockup_ratio_tokens_per_million)?;
+    // Whatever does not fit in a whole lot goes back to the user.
+    let remainder: u64 = unwrap_int!(return_amount.checked_sub(lockup_amount));
+    msg!(
+        "Rolling over {} of {} for {} options",
+        lockup_amount,
+        return_amount,
+        num_staking_options
+    );
+
+    let total_locked: u64 = unwrap_int!(ctx
+        .accounts
+        .gso_state
+        .total_locked
+        .checked_add(lockup_amount));
+    let max_total_locked: u64 = ctx.accounts.gso_state.max_total_locked;
+    if max_total_locked > 0 {
+        invariant!(total_locked <= max_total_locked, TotalCapExceeded);
+    }
+    record_deposit(
+        &ctx.accounts.gso_state,
+        &ctx.accounts.deposit_record.to_account_info(),
+        *ctx.bumps.get("deposit_record").unwrap(),
+        &ctx.accounts.authority.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+        lockup_amount,
+    )?;
+
+    ctx.accounts.gso_state.total_locked = total_locked;
+
+    msg!("Burn old xTokens");
+    anchor_spl::token::burn(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            anchor_spl::token::Burn {
+                mint: ctx.accounts.old_x_base_mint.to_account_info(),
+                from: ctx.accounts.old_user_x_base_account.to_account_info(),
+                authority: ctx.accounts.authority.to_account_info(),
+            },
+        ),
+        amount,
+    )?;
+
+    let old_gso_state_key: Pubkey = ctx.accounts.old_gso_state.key();
+    let old_base_vault_bump: u8 = ctx.accounts.old_gso_state.tier_base_vault_bump(old_tier);
+    let old_base_vault_seeds: &[&[u8]] = &[
+        BASE_VAULT_SEED,
+        &old_gso_state_key.to_bytes(),
+        tier_seed(&old_tier),
+        &[old_base_vault_bump],
+    ];
+
+    msg!("Move tokens to the new vault");
+    anchor_spl::token::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            anchor_spl::token::Transfer {
+                from: ctx.accounts.old_base_vault.to_account_info(),
+                to: ctx.accounts.base_vault.to_account_info(),
+                authority: ctx.accounts.old_base_vault.to_account_info(),
+            },
+            &[old_base_vault_seeds],
+        ),
+        lockup_amount,
+    )?;
+
+    if remainder > 0 {
+        msg!("Return remainder {}", remainder);
+        anchor_spl::token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                anchor_spl::token::Transfer {
+                    from: ctx.accounts.old_base_vault.to_account_info(),
+                    to: ctx.accounts.user_base_account.to_account_info(),
+                    authority: ctx.accounts.old_base_vault.to_account_info(),
+                },
+                &[old_base_vault_seeds],
+            ),
+            remainder,
+        )?;
+    }
+
+    msg!("Mint xTokens");
+    anchor_spl::token::mint_to(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            anchor_spl::token::MintTo {
+                mint: ctx.accounts.x_base_mint.to_account_info(),
+                to: ctx.accounts.user_x_base_account.to_account_info(),
+                authority: ctx.accounts.x_base_mint.to_account_info(),
+            },
+            &[&[
+                X_GSO_SEED,
+                &ctx.accounts.gso_state.key().to_bytes(),
+                tier_seed(&tier),
+                &[ctx.accounts.gso_state.tier_x_base_mint_bump(tier)],
+            ]],
+        ),
+        lockup_amount,
+    )?;
+
+    msg!("CPI into SO");
+    let so_issue_accounts = staking_options::cpi::accounts::Issue {
+        authority: ctx.accounts.so_authority.to_account_info(),
+        state: ctx.accounts.so_state.to_account_info(),
+        option_mint: ctx.accounts.so_option_mint.to_account_info(),
+        user_so_account: ctx.accounts.so_user_option_account.to_account_info(),
+        token_program: ctx.accounts.token_program.to_account_info(),
+    };
+    let cpi_program_issue = ctx.accounts.staking_options_program.to_account_info();
+
+    staking_options::cpi::issue(
+        CpiContext::new_with_signer(
+            cpi_program_issue,
+            so_issue_accounts,
+            &[&[
+                "gso".as_bytes(),
+                &ctx.accounts.gso_state.key().to_bytes(),
+                &[ctx.accounts.gso_state.so_authority_bump],
+            ]],
+        ),
+        num_staking_options,
+        ctx.accounts.gso_state.strike_at(strike_index),
+    )?;
+
+    emit!(Unstaked {
+        gso_state: ctx.accounts.old_gso_state.key(),
+        user: ctx.accounts.authority.key(),
+        tier: old_tier,
+        amount,
+        amount_returned: return_amount,
+        penalty: 0,
+        options_burned: 0,
+        timestamp: now_ts as i64,
+    });
+    emit!(Staked {
+        gso_state: ctx.accounts.gso_state.key(),
+        user: ctx.accounts.authority.key(),
+        tier,
+        strike_index,
+        amount: lockup_amount,
+        options_issued: num_staking_options,
+        lockup_ratio_tokens_per_million,
+        lockup_ratio_num: lockup_ratio.numerator,
+        lockup_ratio_den: lockup_ratio.denominator,
+        timestamp: now_ts as i64,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(amount: u64, old_tier: u8, tier: u8, strike_index: u8)]
+pub struct GSORollover<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// Period being rolled out of.
+    /// =========
+    #[account(
+        mut,
+        seeds = [GSO_STATE_SEED, &old_gso_state.period_num.to_be_bytes(), &old_gso_state.project_name.as_bytes()],
+        bump = old_gso_state.gso_state_bump,
+        constraint = old_gso_state.is_valid_tier(old_tier) @ ErrorCode::InvalidTier,
+    )]
+    pub old_gso_state: Box<Account<'info, GSOState>>,
+    #[account(
+        mut,
+        seeds = [X_GSO_SEED, &old_gso_state.key().to_bytes(), tier_seed(&old_tier)],
+        bump = old_gso_state.tier_x_base_mint_bump(old_tier)
+    )]
+    pub old_x_base_mint: Box<Account<'info, Mint>>,
+    #[account(mut,
+        constraint = old_user_x_base_account.mint == old_x_base_mint.key() @ ErrorCode::InvalidTokenMint
+    )]
+    pub old_user_x_base_account: Box<Account<'info, TokenAccount>>,
+    #[account(mut,
+        seeds = [BASE_VAULT_SEED, &old_gso_state.key().to_bytes(), tier_seed(&old_tier)],
+        bump = old_gso_state.tier_base_vault_bump(old_tier)
+    )]
+    pub old_base_vault: Box<Account<'info, TokenAccount>>,
+
+    /// Receives the part that does not fit in a whole lot.
+    #[account(mut,
+        constraint = user_base_account.mint == old_base_vault.mint @ ErrorCode::InvalidTokenMint
+    )]
+    pub user_base_account: Box<Account<'info, TokenAccount>>,
+
+    /// Period being rolled into.
+    /// =========
+    #[account(
+        mut,
+        seeds = [GSO_STATE_SEED, &gso_state.period_num.to_be_bytes(), &gso_state.project_name.as_bytes()],
+        bump = gso_state.gso_state_bump,
+        constraint = gso_state.is_valid_tier(tier) @ ErrorCode::InvalidTier,
+        constraint = gso_state.is_valid_strike_index(strike_index) @ ErrorCode::InvalidStrikeIndex,
+    )]
+    pub gso_state: Box<Account<'info, GSOState>>,
+
+    #[account(
+        mut,
+        seeds = [DEPOSIT_RECORD_SEED, &gso_state.key().to_bytes(), &authority.key().to_bytes()],
+        bump,
+    )]
+    /// CHECK: Tracks how much this wallet has staked for the per wallet cap.
+    /// Only created and read by record_deposit when there is a cap.
+    pub deposit_record: UncheckedAccount<'info>,
+
+    /// CHECK: Not dangerous. Just an AccountInfo for signing.
+    #[account(mut,
+        seeds = [SO_AUTHORITY_SEED, &gso_state.key().to_bytes()],
+        bump = gso_state.so_authority_bump
+    )]
+    pub so_authority: AccountInfo<'info>,
+
+    /// Verified inside the SO CPI against the strike for strike_index.
+    #[account(mut)]
+    pub so_option_mint: Box<Account<'info, Mint>>,
+    #[account(mut)]
+    pub so_user_option_account: Box<Account<'info, TokenAccount>>,
+    #[account(mut, constraint = so_state.key() == gso_state.staking_options_state)]
+    pub so_state: Box<Account<'info, staking_options::State>>,
+
+    pub staking_options_program: Program<'info, StakingOptionsProgram>,
+
+    #[account(
+        mut,
+        seeds = [X_GSO_SEED, &gso_state.key().to_bytes(), tier_seed(&tier)],
+        bump = gso_state.tier_x_base_mint_bump(tier)
+    )]
+    pub x_base_mint: Box<Account<'info, Mint>>,
+    #[account(mut,
+        constraint = user_x_base_account.mint == x_base_mint.key() @ ErrorCode::InvalidTokenMint
+    )]
+    pub user_x_base_account: Box<Account<'info, TokenAccount>>,
+    #[account(mut,
+        seeds = [BASE_VAULT_SEED, &gso_state.key().to_bytes(), tier_seed(&tier)],
+        bump = gso_state.tier_base_vault_bump(tier)
+    )]
+    pub base_vault: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
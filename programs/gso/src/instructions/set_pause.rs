@@ -0,0 +1,27 @@
+pub use crate::common::*;
+pub use crate::*;
+
+pub fn set_pause(ctx: Context<GSOSetPause>, paused: u8) -> Result<()> {
+    msg!("GSO Set Pause {}", paused);
+    invariant!(
+        paused & !(PAUSE_STAKE | PAUSE_UNSTAKE | PAUSE_WITHDRAW) == 0,
+        InvalidPauseFlags
+    );
+
+    ctx.accounts.gso_state.paused = paused;
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(paused: u8)]
+pub struct GSOSetPause<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [GSO_STATE_SEED, &gso_state.period_num.to_be_bytes(), &gso_state.project_name.as_bytes()],
+        bump = gso_state.gso_state_bump,
+        constraint = gso_state.authority.key() == authority.key())]
+    pub gso_state: Box<Account<'info, GSOState>>,
+}
@@ -0,0 +1,30 @@
+pub use crate::common::*;
+pub use crate::*;
+
+pub fn set_project_defaults(
+    ctx: Context<GSOSetProjectDefaults>,
+    default_lockup_ratio_tokens_per_million: u64,
+    default_lot_size: u64,
+    default_strike: u64,
+) -> Result<()> {
+    msg!("GSO Set Project Defaults");
+
+    ctx.accounts.project.default_lockup_ratio_tokens_per_million =
+        default_lockup_ratio_tokens_per_million;
+    ctx.accounts.project.default_lot_size = default_lot_size;
+    ctx.accounts.project.default_strike = default_strike;
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct GSOSetProjectDefaults<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [PROJECT_SEED, project.project_name.as_bytes()],
+        bump = project.bump,
+        constraint = project.authority.key() == authority.key())]
+    pub project: Box<Account<'info, Project>>,
+}
@@ -5,8 +5,81 @@ pub use crate::common::*;
 pub use crate::errors::ErrorCode;
 pub use crate::*;
 
-pub fn stake(ctx: Context<GSOStake>, amount: u64) -> Result<()> {
+pub fn stake(ctx: Context<GSOStake>, amount: u64, tier: u8, strike_index: u8) -> Result<()> {
     msg!("GSO Stake");
+    invariant!(ctx.accounts.gso_state.paused & PAUSE_STAKE == 0, Paused);
+
+    // Check the subscription window here rather than relying on the SO issue
+    // failing after the tokens are already locked.
+    let now_ts: u64 = Clock::get().unwrap().unix_timestamp as u64;
+    invariant!(
+        now_ts <= ctx.accounts.gso_state.subscription_period_end,
+        SubscriptionEnded
+    );
+    invariant!(
+        now_ts >= ctx.accounts.gso_state.subscription_period_start,
+        SubscriptionNotStarted
+    );
+
+    // Ratio math is done in u128 inside the helpers so tokens like BONK which
+    // really do need all the bits do not overflow or silently truncate.
+    let lockup_ratio_tokens_per_million: u64 =
+        ctx.accounts
+            .gso_state
+            .effective_lockup_ratio(now_ts, tier, strike_index)?;
+    let lockup_ratio: math::Ratio = ctx
+        .accounts
+        .gso_state
+        .lockup_ratio(lockup_ratio_tokens_per_million)?;
+    msg!(
+        "Effective lockup ratio {}/{}",
+        lockup_ratio.numerator,
+        lockup_ratio.denominator
+    );
+    let num_staking_options: u64 = ctx.accounts.gso_state.options_for_lockup(
+        amount,
+        lockup_ratio_tokens_per_million,
+        math::Rounding::Down,
+    )?;
+
+    // SO issues whole lots, so round down to the lot size and only lock the
+    // part of the amount that earns those lots. The rest stays with the user.
+    let num_staking_options: u64 =
+        math::round_down_to_lot(num_staking_options, ctx.accounts.so_state.lot_size)?;
+    invariant!(num_staking_options > 0, StakeTooSmall);
+
+    // Smallest amount that still earns num_staking_options, so rounding up.
+    let lockup_amount: u64 = ctx
+        .accounts
+        .gso_state
+        .lockup_for_options(num_staking_options, lockup_ratio_tokens_per_million)?;
+    msg!(
+        "Locking {} of {} for {} options",
+        lockup_amount,
+        amount,
+        num_staking_options
+    );
+
+    // Check the caps before any tokens move.
+    let total_locked: u64 = unwrap_int!(ctx
+        .accounts
+        .gso_state
+        .total_locked
+        .checked_add(lockup_amount));
+    let max_total_locked: u64 = ctx.accounts.gso_state.max_total_locked;
+    if max_total_locked > 0 {
+        invariant!(total_locked <= max_total_locked, TotalCapExceeded);
+    }
+    record_deposit(
+        &ctx.accounts.gso_state,
+        &ctx.accounts.deposit_record.to_account_info(),
+        *ctx.bumps.get("deposit_record").unwrap(),
+        &ctx.accounts.authority.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+        lockup_amount,
+    )?;
+
+    ctx.accounts.gso_state.total_locked = total_locked;
 
     msg!("Lockup tokens");
     anchor_spl::token::transfer(
@@ -18,7 +91,7 @@ pub fn stake(ctx: Context<GSOStake>, amount: u64) -> Result<()> {
                 authority: ctx.accounts.authority.to_account_info().clone(),
             },
         ),
-        amount,
+        lockup_amount,
     )?;
 
     msg!("Mint xTokens");
@@ -33,23 +106,14 @@ pub fn stake(ctx: Context<GSOStake>, amount: u64) -> Result<()> {
             &[&[
                 X_GSO_SEED,
                 &ctx.accounts.gso_state.key().to_bytes(),
-                &[ctx.accounts.gso_state.x_base_mint_bump],
+                tier_seed(&tier),
+                &[ctx.accounts.gso_state.tier_x_base_mint_bump(tier)],
             ]],
         ),
-        amount,
+        lockup_amount,
     )?;
 
     msg!("CPI into SO");
-    // Convert to u128 to not lose precision with tokens like BONK which really
-    // do need all the bits.
-    let amount_128: u128 = amount as u128;
-    let lockup_ratio_tokens_per_million_128: u128 =
-        ctx.accounts.gso_state.lockup_ratio_tokens_per_million as u128;
-    let num_staking_options_128: u128 = unwrap_int!(unwrap_int!(
-        amount_128.checked_mul(lockup_ratio_tokens_per_million_128)
-    )
-    .checked_div(1_000_000));
-    let num_staking_options: u64 = num_staking_options_128 as u64;
     let so_issue_accounts = staking_options::cpi::accounts::Issue {
         authority: ctx.accounts.so_authority.to_account_info(),
         state: ctx.accounts.so_state.to_account_info(),
@@ -70,24 +134,49 @@ pub fn stake(ctx: Context<GSOStake>, amount: u64) -> Result<()> {
             ]],
         ),
         num_staking_options,
-        ctx.accounts.gso_state.strike,
+        ctx.accounts.gso_state.strike_at(strike_index),
     )?;
 
+    emit!(Staked {
+        gso_state: ctx.accounts.gso_state.key(),
+        user: ctx.accounts.authority.key(),
+        tier,
+        strike_index,
+        amount: lockup_amount,
+        options_issued: num_staking_options,
+        lockup_ratio_tokens_per_million,
+        lockup_ratio_num: lockup_ratio.numerator,
+        lockup_ratio_den: lockup_ratio.denominator,
+        timestamp: now_ts as i64,
+    });
+
     Ok(())
 }
 
 #[derive(Accounts)]
-#[instruction(amount: u64)]
+#[instruction(amount: u64, tier: u8, strike_index: u8)]
 pub struct GSOStake<'info> {
     #[account(mut)]
     pub authority: Signer<'info>,
 
     #[account(
+        mut,
         seeds = [GSO_STATE_SEED, &gso_state.period_num.to_be_bytes(), &gso_state.project_name.as_bytes()],
         bump = gso_state.gso_state_bump,
+        constraint = gso_state.is_valid_tier(tier) @ ErrorCode::InvalidTier,
+        constraint = gso_state.is_valid_strike_index(strike_index) @ ErrorCode::InvalidStrikeIndex,
     )]
     pub gso_state: Box<Account<'info, GSOState>>,
 
+    #[account(
+        mut,
+        seeds = [DEPOSIT_RECORD_SEED, &gso_state.key().to_bytes(), &authority.key().to_bytes()],
+        bump,
+    )]
+    /// CHECK: Tracks how much this wallet has staked for the per wallet cap.
+    /// Only created and read by record_deposit when there is a cap.
+    pub deposit_record: UncheckedAccount<'info>,
+
     /// CHECK: Not dangerous. Just an AccountInfo for signing.
     #[account(mut,
         seeds = [SO_AUTHORITY_SEED, &gso_state.key().to_bytes()],
@@ -95,7 +184,8 @@ pub struct GSOStake<'info> {
     )]
     pub so_authority: AccountInfo<'info>,
 
-    /// The so_option_mint is verified inside the SO CPI.
+    /// The so_option_mint is verified inside the SO CPI against the strike
+    /// for strike_index.
     #[account(mut)]
     pub so_option_mint: Account<'info, Mint>,
     #[account(mut)]
@@ -109,19 +199,24 @@ pub struct GSOStake<'info> {
 
     #[account(
         mut,
-        seeds = [X_GSO_SEED, &gso_state.key().to_bytes()],
-        bump = gso_state.x_base_mint_bump
+        seeds = [X_GSO_SEED, &gso_state.key().to_bytes(), tier_seed(&tier)],
+        bump = gso_state.tier_x_base_mint_bump(tier)
     )]
     pub x_base_mint: Box<Account<'info, Mint>>,
-    #[account(mut)]
+    #[account(mut,
+        constraint = user_base_account.mint == base_vault.mint @ ErrorCode::InvalidTokenMint
+    )]
     pub user_base_account: Box<Account<'info, TokenAccount>>,
-    #[account(mut)]
+    #[account(mut,
+        constraint = user_x_base_account.mint == x_base_mint.key() @ ErrorCode::InvalidTokenMint
+    )]
     pub user_x_base_account: Box<Account<'info, TokenAccount>>,
     #[account(mut,
-        seeds = [BASE_VAULT_SEED, &gso_state.key().to_bytes()],
-        bump = gso_state.base_vault_bump
+        seeds = [BASE_VAULT_SEED, &gso_state.key().to_bytes(), tier_seed(&tier)],
+        bump = gso_state.tier_base_vault_bump(tier)
     )]
     pub base_vault: Box<Account<'info, TokenAccount>>,
 
     pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
 }
@@ -4,21 +4,97 @@ pub use crate::common::*;
 pub use crate::errors::ErrorCode;
 pub use crate::*;
 
-pub fn unstake(ctx: Context<GSOUnstake>, amount: u64) -> Result<()> {
+pub fn unstake<'info>(
+    ctx: Context<'_, '_, '_, 'info, GSOUnstake<'info>>,
+    amount: u64,
+    tier: u8,
+) -> Result<()> {
     msg!("GSO Unstake");
+    invariant!(ctx.accounts.gso_state.paused & PAUSE_UNSTAKE == 0, Paused);
     let now_ts: u64 = Clock::get().unwrap().unix_timestamp as u64;
-    let expiration: u64 = ctx.accounts.gso_state.lockup_period_end;
-    if expiration > 0 {
-        msg!("Now {} Expiration {}", now_ts, expiration);
-        invariant!(expiration < now_ts, NotYetExpired);
+    let expiration: u64 = ctx.accounts.gso_state.tier_lockup_period_end(tier);
+    msg!("Now {} Expiration {}", now_ts, expiration);
+
+    // Before the lockup ends, unstaking is only allowed when the GSO has an
+    // early unstake penalty.
+    let is_early: bool = expiration >= now_ts;
+    if is_early {
+        invariant!(
+            ctx.accounts.gso_state.early_unstake_penalty_bps > 0,
+            NotYetExpired
+        );
+        // A penalty kept in the vault backs the xTokens that are left. Those are
+        // minted 1:1 while the subscription is open, so later stakers would
+        // get a share of it without having been in while it was paid.
+        invariant!(
+            now_ts > ctx.accounts.gso_state.subscription_period_end
+                || ctx.accounts.gso_state.early_unstake_penalty_destination != Pubkey::default(),
+            PenaltyDuringSubscription
+        );
     } else {
-        // TODO: Remove this once all GSOState has a lockup_period_end. This is
-        // for backwards compatibility only.
-        let old_expiration: u64 = ctx.accounts.gso_state.subscription_period_end;
-        msg!("Now {} Expiration {}", now_ts, old_expiration);
-        invariant!(old_expiration < now_ts, NotYetExpired);
+        // With vesting, only the vested part of everything ever staked can
+        // have been unstaked. xTokens are fungible, so this is tracked on the
+        // whole supply rather than per holder. Early unstakes paid a penalty
+        // instead and are not counted.
+        let total_unstaked: u64 = ctx.accounts.gso_state.tier_total_unstaked(tier);
+        let vesting_end: u64 = ctx.accounts.gso_state.tier_vesting_end(tier)?;
+        if vesting_end > now_ts {
+            let total_staked: u64 =
+                unwrap_int!(ctx.accounts.x_base_mint.supply.checked_add(total_unstaked));
+            let vested: u64 = math::vested_amount(total_staked, expiration, vesting_end, now_ts)?;
+            msg!("Vested {} Unstaked {}", vested, total_unstaked);
+            invariant!(
+                unwrap_int!(total_unstaked.checked_add(amount)) <= vested,
+                NotYetVested
+            );
+        }
+        ctx.accounts
+            .gso_state
+            .set_tier_total_unstaked(tier, unwrap_int!(total_unstaked.checked_add(amount)));
+    }
+
+    // Tier 0 can not be unstaked while borrowed tokens are missing from the
+    // vault, otherwise stakers would silently get less than 1:1.
+    if tier == 0 {
+        invariant!(
+            ctx.accounts.gso_state.outstanding_debt == 0,
+            DebtOutstanding
+        );
     }
 
+    // Saturating since states from before total_locked was tracked start at 0.
+    ctx.accounts.gso_state.total_locked =
+        ctx.accounts.gso_state.total_locked.saturating_sub(amount);
+
+    // Pro-rata share of the vault. This is 1:1 unless the vault was slashed.
+    let return_amount: u64 = math::mul_div_floor(
+        amount,
+        ctx.accounts.base_vault.amount,
+        ctx.accounts.x_base_mint.supply,
+    )?;
+
+    // The penalty is rounded up so that splitting an unstake into small
+    // pieces does not avoid it.
+    let penalty_amount: u64 = if is_early {
+        let penalty_bps: u64 = math::early_unstake_penalty_bps(
+            ctx.accounts.gso_state.early_unstake_penalty_bps,
+            ctx.accounts.gso_state.early_unstake_penalty_decay,
+            ctx.accounts.gso_state.subscription_period_start,
+            expiration,
+            now_ts,
+        )?;
+        math::mul_div_ceil(return_amount, penalty_bps, math::BPS_DENOMINATOR)?
+    } else {
+        0
+    };
+    let return_amount: u64 = unwrap_int!(return_amount.checked_sub(penalty_amount));
+    msg!(
+        "Returning {} for {} xTokens with penalty {}",
+        return_amount,
+        amount,
+        penalty_amount
+    );
+
     msg!("Burn xTokens");
     anchor_spl::token::burn(
         CpiContext::new(
@@ -44,40 +120,88 @@ pub fn unstake(ctx: Context<GSOUnstake>, amount: u64) -> Result<()> {
             &[&[
                 BASE_VAULT_SEED,
                 &ctx.accounts.gso_state.key().to_bytes(),
-                &[ctx.accounts.gso_state.base_vault_bump],
+                tier_seed(&tier),
+                &[ctx.accounts.gso_state.tier_base_vault_bump(tier)],
             ]],
         ),
-        amount,
+        return_amount,
     )?;
 
+    // The penalty destination is passed as the only remaining account. When
+    // there is no destination the penalty stays in the vault and goes to the
+    // remaining stakers.
+    let penalty_destination: Pubkey = ctx.accounts.gso_state.early_unstake_penalty_destination;
+    if penalty_amount > 0 && penalty_destination != Pubkey::default() {
+        invariant!(
+            ctx.remaining_accounts.len() == 1
+                && ctx.remaining_accounts[0].key() == penalty_destination,
+            InvalidPenaltyParams
+        );
+        msg!("Send penalty");
+        anchor_spl::token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                anchor_spl::token::Transfer {
+                    from: ctx.accounts.base_vault.to_account_info(),
+                    to: ctx.remaining_accounts[0].clone(),
+                    authority: ctx.accounts.base_vault.to_account_info().clone(),
+                },
+                &[&[
+                    BASE_VAULT_SEED,
+                    &ctx.accounts.gso_state.key().to_bytes(),
+                    tier_seed(&tier),
+                    &[ctx.accounts.gso_state.tier_base_vault_bump(tier)],
+                ]],
+            ),
+            penalty_amount,
+        )?;
+    }
+
+    emit!(Unstaked {
+        gso_state: ctx.accounts.gso_state.key(),
+        user: ctx.accounts.authority.key(),
+        tier,
+        amount,
+        amount_returned: return_amount,
+        penalty: penalty_amount,
+        options_burned: 0,
+        timestamp: now_ts as i64,
+    });
+
     Ok(())
 }
 
 #[derive(Accounts)]
-#[instruction(amount: u64)]
+#[instruction(amount: u64, tier: u8)]
 pub struct GSOUnstake<'info> {
     #[account(mut)]
     pub authority: Signer<'info>,
 
     #[account(
+        mut,
         seeds = [GSO_STATE_SEED, &gso_state.period_num.to_be_bytes(), &gso_state.project_name.as_bytes()],
         bump = gso_state.gso_state_bump,
+        constraint = gso_state.is_valid_tier(tier) @ ErrorCode::InvalidTier,
     )]
     pub gso_state: Box<Account<'info, GSOState>>,
 
     #[account(
         mut,
-        seeds = [X_GSO_SEED, &gso_state.key().to_bytes()],
-        bump = gso_state.x_base_mint_bump
+        seeds = [X_GSO_SEED, &gso_state.key().to_bytes(), tier_seed(&tier)],
+        bump = gso_state.tier_x_base_mint_bump(tier)
     )]
     pub x_base_mint: Box<Account<'info, Mint>>,
-    #[account(mut)]
+    #[account(mut,
+        constraint = user_base_account.mint == base_vault.mint @ ErrorCode::InvalidTokenMint
+    )]
     pub user_base_account: Box<Account<'info, TokenAccount>>,
-    #[account(mut)]
+    #[account(mut,
+        constraint = user_x_base_account.mint == x_base_mint.key() @ ErrorCode::InvalidTokenMint
+    )]
     pub user_x_base_account: Box<Account<'info, TokenAccount>>,
     #[account(mut,
-        seeds = [BASE_VAULT_SEED, &gso_state.key().to_bytes()],
-        bump = gso_state.base_vault_bump
+        seeds = [BASE_VAULT_SEED, &gso_state.key().to_bytes(), tier_seed(&tier)],
+        bump = gso_state.tier_base_vault_bump(tier)
     )]
     pub base_vault: Box<Account<'info, TokenAccount>>,
 
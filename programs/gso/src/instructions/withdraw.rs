@@ -6,6 +6,10 @@ pub use crate::*;
 
 pub fn withdraw(ctx: Context<GSOWithdraw>) -> Result<()> {
     msg!("GSO Withdraw");
+    invariant!(ctx.accounts.gso_state.paused & PAUSE_WITHDRAW == 0, Paused);
+
+    // SO withdraw sends everything left in its vault.
+    let amount: u64 = ctx.accounts.so_base_vault.amount;
 
     msg!("CPI into SO");
     let so_withdraw_accounts = staking_options::cpi::accounts::Withdraw {
@@ -28,6 +32,13 @@ pub fn withdraw(ctx: Context<GSOWithdraw>) -> Result<()> {
         ]],
     ))?;
 
+    emit!(Withdrawn {
+        gso_state: ctx.accounts.gso_state.key(),
+        authority: ctx.accounts.authority.key(),
+        amount,
+        timestamp: Clock::get().unwrap().unix_timestamp,
+    });
+
     Ok(())
 }
 
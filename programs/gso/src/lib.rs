@@ -3,11 +3,15 @@ use vipers::prelude::*;
 
 mod common;
 mod errors;
+mod events;
 mod instructions;
+mod validation;
 
 pub use crate::common::*;
 pub use crate::errors::ErrorCode;
+pub use crate::events::*;
 pub use crate::instructions::*;
+pub use crate::validation::*;
 
 #[cfg(not(feature = "no-entrypoint"))]
 solana_security_txt::security_txt! {
@@ -28,15 +32,73 @@ pub mod gso {
     use super::*;
 
     // The function of GSO is to provide a wrapper around StakingOptions with
-    // the additional state and functionality for locking tokens. In the future,
-    // capability may be added so that GSO staked tokens can be used by the
-    // project in some way, like insurance or loans with slashing.
+    // the additional state and functionality for locking tokens. GSO staked
+    // tokens can optionally back insurance for the project through slashing,
+    // or be lent to the project through a credit line.
 
     // Note that GSO is a legacy name, it is a less general version of staking
     // options. GSO is a specific implementation of a use case of the SO
     // primitive.
+    // InitProject. Claims a project name for the authority. Every period of
+    // the project is configured against it, in order starting after
+    // latest_period. A name that GSOs from before projects already use can only
+    // be claimed by the authority of the one for latest_period, and a new name
+    // must start from 0. That authority can also take the project back from
+    // whoever claimed it with a lower latest_period.
+    pub fn init_project(
+        ctx: Context<GSOInitProject>,
+        project_name: String,
+        latest_period: u64,
+        default_lockup_ratio_tokens_per_million: u64,
+        default_lot_size: u64,
+        default_strike: u64,
+    ) -> Result<()> {
+        init_project::init_project(
+            ctx,
+            project_name,
+            latest_period,
+            default_lockup_ratio_tokens_per_million,
+            default_lot_size,
+            default_strike,
+        )
+    }
+
+    // SetProjectDefaults. Updates the parameters clients suggest for the next
+    // period.
+    pub fn set_project_defaults(
+        ctx: Context<GSOSetProjectDefaults>,
+        default_lockup_ratio_tokens_per_million: u64,
+        default_lot_size: u64,
+        default_strike: u64,
+    ) -> Result<()> {
+        set_project_defaults::set_project_defaults(
+            ctx,
+            default_lockup_ratio_tokens_per_million,
+            default_lot_size,
+            default_strike,
+        )
+    }
+
+    // ProposeProjectAuthority. First step of handing the project to a new
+    // authority, for example together with its GSOs. Nothing changes until the
+    // new authority accepts.
+    pub fn propose_project_authority(
+        ctx: Context<GSOProposeProjectAuthority>,
+        pending_authority: Pubkey,
+    ) -> Result<()> {
+        propose_project_authority::propose_project_authority(ctx, pending_authority)
+    }
+
+    // AcceptProjectAuthority. The pending authority signs to take over the
+    // project, and with it the config of later periods.
+    pub fn accept_project_authority(ctx: Context<GSOAcceptProjectAuthority>) -> Result<()> {
+        accept_project_authority::accept_project_authority(ctx)
+    }
+
     // Config. Minimal management in the GSO wrapper, most of the config work is
-    // done in staking options itself.
+    // done in staking options itself. Requires the Project for the name and
+    // period_num must be the next period of it. See ConfigOptions for the rest.
+    #[allow(clippy::too_many_arguments)]
     pub fn config(
         ctx: Context<GSOConfig>,
         period_num: u64,
@@ -49,6 +111,7 @@ pub mod gso {
         project_name: String,
         strike_price: u64,
         so_authority_bump: u8,
+        options: ConfigOptions,
     ) -> Result<()> {
         config::config(
             ctx,
@@ -62,11 +125,14 @@ pub mod gso {
             project_name,
             strike_price,
             so_authority_bump,
+            options,
         )
     }
 
     // ConfigV2. Same as config except that the SO base mint does not need to be
-    // the same as the lockup mint.
+    // the same as the lockup mint. The lockup ratio is in whole tokens, so the
+    // two mints can have different decimals.
+    #[allow(clippy::too_many_arguments)]
     pub fn config_v2(
         ctx: Context<GSOConfigV2>,
         period_num: u64,
@@ -79,6 +145,7 @@ pub mod gso {
         project_name: String,
         strike_price: u64,
         so_authority_bump: u8,
+        options: ConfigOptions,
     ) -> Result<()> {
         config_v2::config_v2(
             ctx,
@@ -92,28 +159,156 @@ pub mod gso {
             project_name,
             strike_price,
             so_authority_bump,
+            options,
         )
     }
 
     // Stake. This is a liquid staking, so the user is able to split up and sell
     // their claim to their tokens back at the end of the staking period. The
-    // receipt tokens of the base token deposit are xBaseTokens.
-    pub fn stake(ctx: Context<GSOStake>, amount: u64) -> Result<()> {
-        stake::stake(ctx, amount)
+    // receipt tokens of the base token deposit are xBaseTokens. Each lockup
+    // tier has its own xBaseTokens. The strike_index picks which strike the
+    // options are issued at.
+    pub fn stake(ctx: Context<GSOStake>, amount: u64, tier: u8, strike_index: u8) -> Result<()> {
+        stake::stake(ctx, amount, tier, strike_index)
     }
 
     // Unstake. The holder of the receipt tokens from staking is able to redeem
     // for their tokens back. There is no verification that it is the same as
-    // the depositor since it is a liquid staking.
-    pub fn unstake(ctx: Context<GSOUnstake>, amount: u64) -> Result<()> {
-        unstake::unstake(ctx, amount)
+    // the depositor since it is a liquid staking. If the vault was slashed,
+    // each receipt token gets its pro-rata share of what is left. Before the
+    // lockup ends this is only allowed with an early unstake penalty, which
+    // goes to the penalty destination passed as a remaining account or stays
+    // in the vault, in which case only once the subscription has ended.
+    pub fn unstake<'info>(
+        ctx: Context<'_, '_, '_, 'info, GSOUnstake<'info>>,
+        amount: u64,
+        tier: u8,
+    ) -> Result<()> {
+        unstake::unstake(ctx, amount, tier)
+    }
+
+    // EarlyUnstake. Unstake before the lockup ends by also handing back the
+    // options that the amount earned. The options are burned. They must be
+    // options of the strike that earns the most per token, since it is not
+    // known which strike the xTokens were staked at.
+    pub fn early_unstake(ctx: Context<GSOEarlyUnstake>, amount: u64, tier: u8) -> Result<()> {
+        early_unstake::early_unstake(ctx, amount, tier)
+    }
+
+    // Rollover. Unstake an expired position and stake it into a later period
+    // of the same project in one go. Tokens move directly between the vaults
+    // and anything that does not fit in a whole lot goes back to the user.
+    pub fn rollover(
+        ctx: Context<GSORollover>,
+        amount: u64,
+        old_tier: u8,
+        tier: u8,
+        strike_index: u8,
+    ) -> Result<()> {
+        rollover::rollover(ctx, amount, old_tier, tier, strike_index)
     }
 
     pub fn withdraw(ctx: Context<GSOWithdraw>) -> Result<()> {
         withdraw::withdraw(ctx)
     }
 
-    pub fn name_tokens(ctx: Context<GSONameTokens>) -> Result<()> {
+    // NameTokens. Names the option mint for every strike and the xBaseTokens
+    // of every tier. Option mints and metadata for additional strikes, then
+    // xBase mints and metadata for additional tiers, are passed as pairs of
+    // remaining accounts. Anything already named is skipped, so it can be run
+    // again after add_strike or add_tier.
+    pub fn name_tokens<'info>(ctx: Context<'_, '_, '_, 'info, GSONameTokens<'info>>) -> Result<()> {
         name_tokens::name_tokens(ctx)
     }
+
+    // Close. Once every xToken has been unstaked and the lockup is over, the
+    // authority can reclaim the rent from the GSOState and base vaults. Vaults
+    // for additional tiers are passed as remaining accounts.
+    pub fn close<'info>(ctx: Context<'_, '_, '_, 'info, GSOClose<'info>>) -> Result<()> {
+        close::close(ctx)
+    }
+
+    // ProposeAuthority. First step of handing the GSO to a new authority, for
+    // example a multisig. Nothing changes until the new authority accepts.
+    pub fn propose_authority(
+        ctx: Context<GSOProposeAuthority>,
+        pending_authority: Pubkey,
+    ) -> Result<()> {
+        propose_authority::propose_authority(ctx, pending_authority)
+    }
+
+    // AcceptAuthority. The pending authority signs to take over the GSO.
+    pub fn accept_authority(ctx: Context<GSOAcceptAuthority>) -> Result<()> {
+        accept_authority::accept_authority(ctx)
+    }
+
+    // AddTier. Adds another lockup tier with its own lockup end, lockup ratio,
+    // xBaseTokens and vault. Tiers are numbered from 1, tier 0 is the one from
+    // config.
+    pub fn add_tier(
+        ctx: Context<GSOAddTier>,
+        tier: u8,
+        lockup_period_end: u64,
+        lockup_ratio_tokens_per_million: u64,
+    ) -> Result<()> {
+        add_tier::add_tier(
+            ctx,
+            tier,
+            lockup_period_end,
+            lockup_ratio_tokens_per_million,
+        )
+    }
+
+    // AddStrike. Registers another strike on the SO state with its own lockup
+    // ratio. Strike indexes are numbered from 1, index 0 is the strike from
+    // config.
+    pub fn add_strike(
+        ctx: Context<GSOAddStrike>,
+        strike: u64,
+        lockup_ratio_tokens_per_million: u64,
+    ) -> Result<()> {
+        add_strike::add_strike(ctx, strike, lockup_ratio_tokens_per_million)
+    }
+
+    // ProposeSlash. Authority proposes moving amount from a tier's base vault
+    // to the slash destination once the subscription has ended. Can be
+    // executed once the timelock passes.
+    pub fn propose_slash(ctx: Context<GSOProposeSlash>, tier: u8, amount: u64) -> Result<()> {
+        propose_slash::propose_slash(ctx, tier, amount)
+    }
+
+    // ExecuteSlash. Carries out the pending slash after the timelock.
+    pub fn execute_slash(ctx: Context<GSOExecuteSlash>, tier: u8) -> Result<()> {
+        execute_slash::execute_slash(ctx, tier)
+    }
+
+    // Borrow. Authority draws tokens from the tier 0 base vault up to the
+    // credit limit. Must be repaid before the lockup ends or unstaking is
+    // blocked.
+    pub fn borrow(ctx: Context<GSOBorrow>, amount: u64) -> Result<()> {
+        borrow::borrow(ctx, amount)
+    }
+
+    // Repay. Anyone can return borrowed tokens to the base vault.
+    pub fn repay(ctx: Context<GSORepay>, amount: u64) -> Result<()> {
+        repay::repay(ctx, amount)
+    }
+
+    // MarkDefault. Permissionless record that the debt was not repaid by the
+    // end of the lockup.
+    pub fn mark_default(ctx: Context<GSOMarkDefault>) -> Result<()> {
+        mark_default::mark_default(ctx)
+    }
+
+    // MigrateLockupMint. Permissionless fill of lockup_mint for states that
+    // were configured before it was stored, from the mint of the tier 0 vault.
+    pub fn migrate_lockup_mint(ctx: Context<GSOMigrateLockupMint>) -> Result<()> {
+        migrate_lockup_mint::migrate_lockup_mint(ctx)
+    }
+
+    // SetPause. Authority can pause stake, unstake and withdraw independently
+    // by setting the matching PAUSE_* bits. Zero unpauses everything.
+    pub fn set_pause(ctx: Context<GSOSetPause>, paused: u8) -> Result<()> {
+        set_pause::set_pause(ctx, paused)
+    }
 }
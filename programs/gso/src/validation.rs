@@ -0,0 +1,332 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::pubkey::MAX_SEED_LEN;
+use vipers::prelude::*;
+
+use crate::common::*;
+
+// Checks on the config params shared by config and config_v2. Anything that
+// would only fail later in the SO CPI or leave an unusable GSO is rejected up
+// front with a specific error.
+
+// The SO state is seeded by the SO name, which adds "GSO" before the project
+// name and the period after it, so that is what has to fit in a single seed.
+// This leaves room for periods up to 99999.
+pub const MAX_PROJECT_NAME_LEN: usize = MAX_SEED_LEN - "GSO".len() - "-99999".len();
+
+// More than a thousand options per token is certainly a mistake in the
+// decimals of the ratio.
+pub const MAX_LOCKUP_RATIO_TOKENS_PER_MILLION: u64 = 1_000 * math::LOCKUP_RATIO_DENOMINATOR;
+
+pub struct ConfigParams<'a> {
+    pub period_num: u64,
+    pub lockup_ratio_tokens_per_million: u64,
+    pub lockup_period_end: u64,
+    pub option_expiration: u64,
+    pub subscription_period_end: u64,
+    pub lot_size: u64,
+    pub num_tokens: u64,
+    pub project_name: &'a str,
+    pub strike: u64,
+    pub options: ConfigOptions,
+}
+
+impl<'a> ConfigParams<'a> {
+    pub fn validate(&self) -> Result<()> {
+        validate_project_name(self.project_name)?;
+        invariant!(
+            so_name(self.project_name, self.period_num).len() <= MAX_SEED_LEN,
+            InvalidProjectName
+        );
+        invariant!(
+            self.lockup_period_end >= self.subscription_period_end,
+            InvalidLockupEnd
+        );
+        // Options that expire before the subscription ends could never be
+        // exercised by the last stakers.
+        invariant!(
+            self.option_expiration >= self.subscription_period_end,
+            InvalidOptionExpiration
+        );
+        if self.options.subscription_period_start > 0 {
+            invariant!(
+                self.options.subscription_period_start < self.subscription_period_end,
+                InvalidSubscriptionStart
+            );
+        }
+        invariant!(self.strike > 0, InvalidStrike);
+        invariant!(self.lot_size > 0, InvalidLotSize);
+        // SO mints options in whole lots, so any remainder would be stuck in
+        // the SO vault.
+        invariant!(
+            self.num_tokens.checked_rem(self.lot_size) == Some(0),
+            InvalidNumTokens
+        );
+        validate_lockup_ratio(self.lockup_ratio_tokens_per_million)?;
+        // The exact ratio is held to the same bound of a thousand options per
+        // token as the per million one.
+        if self.options.lockup_ratio_den > 0 {
+            invariant!(
+                self.options.lockup_ratio_num > 0
+                    && (self.options.lockup_ratio_num as u128)
+                        <= (self.options.lockup_ratio_den as u128) * 1_000,
+                InvalidLockupRatio
+            );
+        }
+        // A schedule decays so that earlier stakers earn more options.
+        if self.options.lockup_ratio_schedule != RATIO_SCHEDULE_FLAT {
+            invariant!(
+                self.options.lockup_ratio_schedule == RATIO_SCHEDULE_LINEAR
+                    || self.options.lockup_ratio_schedule == RATIO_SCHEDULE_STEPWISE,
+                InvalidRatioSchedule
+            );
+            invariant!(
+                self.options.lockup_ratio_end_tokens_per_million
+                    <= self.lockup_ratio_tokens_per_million,
+                InvalidRatioSchedule
+            );
+        }
+        if self.options.lockup_ratio_schedule == RATIO_SCHEDULE_STEPWISE {
+            invariant!(self.options.lockup_ratio_steps >= 2, InvalidRatioSchedule);
+        }
+        invariant!(
+            self.options.max_slash_bps <= math::BPS_DENOMINATOR,
+            InvalidSlashParams
+        );
+        // A slash must be visible for a while before it executes and must have
+        // somewhere to go.
+        if self.options.max_slash_bps > 0 {
+            invariant!(
+                self.options.slash_timelock >= MIN_SLASH_TIMELOCK,
+                InvalidSlashParams
+            );
+            invariant!(
+                self.options.slash_destination != Pubkey::default(),
+                InvalidSlashParams
+            );
+        }
+        if self.options.vesting_end > 0 {
+            invariant!(
+                self.options.vesting_end > self.lockup_period_end,
+                InvalidVestingEnd
+            );
+        }
+        invariant!(
+            self.options.early_unstake_penalty_bps <= math::BPS_DENOMINATOR,
+            InvalidPenaltyParams
+        );
+        Ok(())
+    }
+}
+
+pub fn validate_project_name(project_name: &str) -> Result<()> {
+    invariant!(
+        !project_name.is_empty() && project_name.len() <= MAX_PROJECT_NAME_LEN,
+        InvalidProjectName
+    );
+    Ok(())
+}
+
+// Tier and strike ratios multiply the base ratio, so they are held to the same
+// bounds.
+pub fn validate_lockup_ratio(lockup_ratio_tokens_per_million: u64) -> Result<()> {
+    invariant!(
+        lockup_ratio_tokens_per_million > 0
+            && lockup_ratio_tokens_per_million <= MAX_LOCKUP_RATIO_TOKENS_PER_MILLION,
+        InvalidLockupRatio
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn valid_params() -> ConfigParams<'static> {
+        ConfigParams {
+            period_num: 1,
+            lockup_ratio_tokens_per_million: 200_000,
+            lockup_period_end: 200,
+            option_expiration: 300,
+            subscription_period_end: 100,
+            lot_size: 1_000,
+            num_tokens: 1_000_000,
+            project_name: "PROJECT",
+            strike: 50_000,
+            options: ConfigOptions {
+                lockup_ratio_schedule: RATIO_SCHEDULE_FLAT,
+                ..ConfigOptions::default()
+            },
+        }
+    }
+
+    fn error_name(result: Result<()>) -> String {
+        match result {
+            Err(Error::AnchorError(err)) => err.error_name,
+            _ => panic!("Expected an error"),
+        }
+    }
+
+    #[test]
+    fn test_valid_params() {
+        assert!(valid_params().validate().is_ok());
+    }
+
+    #[test]
+    fn test_lockup_end_before_subscription_end() {
+        let mut params = valid_params();
+        params.lockup_period_end = params.subscription_period_end;
+        assert!(params.validate().is_ok());
+        params.lockup_period_end = params.subscription_period_end - 1;
+        assert_eq!(error_name(params.validate()), "InvalidLockupEnd");
+    }
+
+    #[test]
+    fn test_option_expiration_before_subscription_end() {
+        let mut params = valid_params();
+        params.option_expiration = params.subscription_period_end;
+        assert!(params.validate().is_ok());
+        params.option_expiration = params.subscription_period_end - 1;
+        assert_eq!(error_name(params.validate()), "InvalidOptionExpiration");
+    }
+
+    #[test]
+    fn test_subscription_start_after_end() {
+        let mut params = valid_params();
+        params.options.subscription_period_start = params.subscription_period_end;
+        assert_eq!(error_name(params.validate()), "InvalidSubscriptionStart");
+    }
+
+    #[test]
+    fn test_zero_strike() {
+        let mut params = valid_params();
+        params.strike = 0;
+        assert_eq!(error_name(params.validate()), "InvalidStrike");
+    }
+
+    #[test]
+    fn test_zero_lot_size() {
+        let mut params = valid_params();
+        params.lot_size = 0;
+        assert_eq!(error_name(params.validate()), "InvalidLotSize");
+    }
+
+    #[test]
+    fn test_num_tokens_not_multiple_of_lot_size() {
+        let mut params = valid_params();
+        params.num_tokens = params.lot_size * 3 + 1;
+        assert_eq!(error_name(params.validate()), "InvalidNumTokens");
+        params.num_tokens = 0;
+        assert!(params.validate().is_ok());
+    }
+
+    #[test]
+    fn test_lockup_ratio_bounds() {
+        let mut params = valid_params();
+        params.lockup_ratio_tokens_per_million = 0;
+        assert_eq!(error_name(params.validate()), "InvalidLockupRatio");
+        params.lockup_ratio_tokens_per_million = MAX_LOCKUP_RATIO_TOKENS_PER_MILLION;
+        assert!(params.validate().is_ok());
+        params.lockup_ratio_tokens_per_million = MAX_LOCKUP_RATIO_TOKENS_PER_MILLION + 1;
+        assert_eq!(error_name(params.validate()), "InvalidLockupRatio");
+    }
+
+    #[test]
+    fn test_rational_lockup_ratio_bounds() {
+        let mut params = valid_params();
+        params.options.lockup_ratio_den = 3;
+        params.options.lockup_ratio_num = 0;
+        assert_eq!(error_name(params.validate()), "InvalidLockupRatio");
+        params.options.lockup_ratio_num = 1;
+        assert!(params.validate().is_ok());
+        params.options.lockup_ratio_num = 3_000;
+        assert!(params.validate().is_ok());
+        params.options.lockup_ratio_num = 3_001;
+        assert_eq!(error_name(params.validate()), "InvalidLockupRatio");
+        params.options.lockup_ratio_den = u64::MAX;
+        params.options.lockup_ratio_num = u64::MAX;
+        assert!(params.validate().is_ok());
+    }
+
+    #[test]
+    fn test_ratio_schedule() {
+        let mut params = valid_params();
+        params.options.lockup_ratio_schedule = RATIO_SCHEDULE_LINEAR;
+        params.options.lockup_ratio_end_tokens_per_million =
+            params.lockup_ratio_tokens_per_million + 1;
+        assert_eq!(error_name(params.validate()), "InvalidRatioSchedule");
+        params.options.lockup_ratio_end_tokens_per_million = 0;
+        assert!(params.validate().is_ok());
+        params.options.lockup_ratio_schedule = RATIO_SCHEDULE_STEPWISE;
+        params.options.lockup_ratio_steps = 1;
+        assert_eq!(error_name(params.validate()), "InvalidRatioSchedule");
+        params.options.lockup_ratio_schedule = RATIO_SCHEDULE_STEPWISE + 1;
+        assert_eq!(error_name(params.validate()), "InvalidRatioSchedule");
+    }
+
+    #[test]
+    fn test_slash_bps() {
+        let mut params = valid_params();
+        params.options.max_slash_bps = math::BPS_DENOMINATOR + 1;
+        assert_eq!(error_name(params.validate()), "InvalidSlashParams");
+    }
+
+    #[test]
+    fn test_slash_timelock() {
+        let mut params = valid_params();
+        params.options.max_slash_bps = 1_000;
+        params.options.slash_destination = Pubkey::new_unique();
+        assert_eq!(error_name(params.validate()), "InvalidSlashParams");
+        params.options.slash_timelock = MIN_SLASH_TIMELOCK - 1;
+        assert_eq!(error_name(params.validate()), "InvalidSlashParams");
+        params.options.slash_timelock = MIN_SLASH_TIMELOCK;
+        assert!(params.validate().is_ok());
+    }
+
+    #[test]
+    fn test_slash_destination() {
+        let mut params = valid_params();
+        params.options.max_slash_bps = 1_000;
+        params.options.slash_timelock = MIN_SLASH_TIMELOCK;
+        assert_eq!(error_name(params.validate()), "InvalidSlashParams");
+        params.options.slash_destination = Pubkey::new_unique();
+        assert!(params.validate().is_ok());
+    }
+
+    #[test]
+    fn test_vesting_end() {
+        let mut params = valid_params();
+        params.options.vesting_end = params.lockup_period_end;
+        assert_eq!(error_name(params.validate()), "InvalidVestingEnd");
+    }
+
+    #[test]
+    fn test_penalty_bps() {
+        let mut params = valid_params();
+        params.options.early_unstake_penalty_bps = math::BPS_DENOMINATOR + 1;
+        assert_eq!(error_name(params.validate()), "InvalidPenaltyParams");
+    }
+
+    #[test]
+    fn test_project_name() {
+        let mut params = valid_params();
+        let longest: String = "N".repeat(MAX_PROJECT_NAME_LEN);
+        params.project_name = &longest;
+        assert!(params.validate().is_ok());
+        params.period_num = 99_999;
+        assert!(params.validate().is_ok());
+        params.period_num = 100_000;
+        assert_eq!(error_name(params.validate()), "InvalidProjectName");
+        params.period_num = 1;
+        let too_long: String = "N".repeat(MAX_PROJECT_NAME_LEN + 1);
+        params.project_name = &too_long;
+        assert_eq!(error_name(params.validate()), "InvalidProjectName");
+        params.project_name = "";
+        assert_eq!(error_name(params.validate()), "InvalidProjectName");
+        // The limit is in bytes, not characters.
+        let multibyte: String = "é".repeat(MAX_PROJECT_NAME_LEN / 2 + 1);
+        assert_eq!(
+            error_name(validate_project_name(&multibyte)),
+            "InvalidProjectName"
+        );
+    }
+}
@@ -0,0 +1,409 @@
+// Shared setup for the solana-program-test suite. GSO, staking options,
+// Metaplex and the SPL programs all run as native programs, so the suite needs
+// neither a BPF build nor a validator cloning programs from devnet.
+
+#![allow(dead_code)]
+
+use std::mem::transmute;
+
+use anchor_lang::AccountDeserialize;
+use solana_program_test::{ProgramTest, ProgramTestContext};
+use solana_sdk::account::Account;
+use solana_sdk::account_info::AccountInfo;
+use solana_sdk::clock::Clock;
+use solana_sdk::entrypoint::ProgramResult;
+use solana_sdk::instruction::{Instruction, InstructionError};
+use solana_sdk::program_pack::Pack;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::system_instruction;
+use solana_sdk::transaction::{Transaction, TransactionError};
+use solana_sdk::transport::{Result as TransportResult, TransportError};
+
+use gso::{ConfigOptions, ErrorCode, GSOState, Project};
+use gso_client::instruction::ConfigAccounts;
+use gso_client::*;
+
+#[macro_use]
+pub mod native;
+
+pub const NUM_TOKENS: u64 = 1_000_000;
+pub const NUM_STAKE: u64 = 1_000_000;
+pub const LOCKUP_RATIO_TOKENS_PER_MILLION: u64 = 200_000;
+pub const STRIKE: u64 = 50_000;
+pub const LOT_SIZE: u64 = 1;
+pub const SUBSCRIPTION_SECONDS: u64 = 100;
+
+// Metaplex ties every lifetime to the accounts, which a native entrypoint can
+// not express.
+fn process_token_metadata_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    input: &[u8],
+) -> ProgramResult {
+    let accounts: &[AccountInfo] = unsafe { transmute::<&[AccountInfo], &[AccountInfo]>(accounts) };
+    mpl_token_metadata::processor::process_instruction(program_id, accounts, input)
+}
+
+pub async fn start() -> ProgramTestContext {
+    let mut program_test: ProgramTest = ProgramTest::default();
+    program_test.prefer_bpf(false);
+    program_test.add_program("gso", gso::ID, native_processor!(gso::entry));
+    program_test.add_program(
+        "staking_options",
+        staking_options::ID,
+        native_processor!(staking_options::entry),
+    );
+    program_test.add_program(
+        "mpl_token_metadata",
+        mpl_token_metadata::ID,
+        native_processor!(process_token_metadata_instruction),
+    );
+    // The bundled SPL programs are BPF, which the 1.9 interpreter crashes on
+    // with current compilers, so replace them with the native ones as well.
+    program_test.add_program(
+        "spl_token",
+        spl_token::ID,
+        native_processor!(spl_token::processor::Processor::process),
+    );
+    program_test.add_program(
+        "spl_associated_token_account",
+        spl_associated_token_account::ID,
+        native_processor!(spl_associated_token_account::processor::process_instruction),
+    );
+    let context: ProgramTestContext = program_test.start_with_context().await;
+    native::install_syscall_stubs();
+    context
+}
+
+// Sends the instructions with the context payer as fee payer and signer.
+pub async fn process(
+    context: &mut ProgramTestContext,
+    instructions: &[Instruction],
+    signers: &[&Keypair],
+) -> TransportResult<()> {
+    let mut all_signers: Vec<&Keypair> = vec![&context.payer];
+    all_signers.extend_from_slice(signers);
+    let blockhash = context.banks_client.get_latest_blockhash().await?;
+    let transaction: Transaction = Transaction::new_signed_with_payer(
+        instructions,
+        Some(&context.payer.pubkey()),
+        &all_signers,
+        blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await
+}
+
+pub fn assert_gso_error(result: TransportResult<()>, error_code: ErrorCode) {
+    match result {
+        Err(TransportError::TransactionError(TransactionError::InstructionError(
+            _,
+            InstructionError::Custom(code),
+        ))) => assert_eq!(code, u32::from(error_code)),
+        _ => panic!("Expected {:?}, got {:?}", error_code, result),
+    }
+}
+
+pub async fn create_mint(context: &mut ProgramTestContext, decimals: u8) -> Pubkey {
+    let mint: Keypair = Keypair::new();
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let authority: Pubkey = context.payer.pubkey();
+    process(
+        context,
+        &[
+            system_instruction::create_account(
+                &authority,
+                &mint.pubkey(),
+                rent.minimum_balance(spl_token::state::Mint::LEN),
+                spl_token::state::Mint::LEN as u64,
+                &spl_token::ID,
+            ),
+            spl_token::instruction::initialize_mint(
+                &spl_token::ID,
+                &mint.pubkey(),
+                &authority,
+                None,
+                decimals,
+            )
+            .unwrap(),
+        ],
+        &[&mint],
+    )
+    .await
+    .unwrap();
+    mint.pubkey()
+}
+
+// A token account owned by the payer that is not the associated one.
+pub async fn create_token_account(context: &mut ProgramTestContext, mint: &Pubkey) -> Pubkey {
+    let account: Keypair = Keypair::new();
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let owner: Pubkey = context.payer.pubkey();
+    process(
+        context,
+        &[
+            system_instruction::create_account(
+                &owner,
+                &account.pubkey(),
+                rent.minimum_balance(spl_token::state::Account::LEN),
+                spl_token::state::Account::LEN as u64,
+                &spl_token::ID,
+            ),
+            spl_token::instruction::initialize_account(
+                &spl_token::ID,
+                &account.pubkey(),
+                mint,
+                &owner,
+            )
+            .unwrap(),
+        ],
+        &[&account],
+    )
+    .await
+    .unwrap();
+    account.pubkey()
+}
+
+pub async fn create_associated_token_account(
+    context: &mut ProgramTestContext,
+    mint: &Pubkey,
+) -> Pubkey {
+    let owner: Pubkey = context.payer.pubkey();
+    process(
+        context,
+        &[
+            spl_associated_token_account::instruction::create_associated_token_account(
+                &owner, &owner, mint,
+            ),
+        ],
+        &[],
+    )
+    .await
+    .unwrap();
+    spl_associated_token_account::get_associated_token_address(&owner, mint)
+}
+
+pub async fn mint_to(
+    context: &mut ProgramTestContext,
+    mint: &Pubkey,
+    account: &Pubkey,
+    amount: u64,
+) {
+    let authority: Pubkey = context.payer.pubkey();
+    process(
+        context,
+        &[
+            spl_token::instruction::mint_to(&spl_token::ID, mint, account, &authority, &[], amount)
+                .unwrap(),
+        ],
+        &[],
+    )
+    .await
+    .unwrap();
+}
+
+pub async fn get_account(context: &mut ProgramTestContext, address: &Pubkey) -> Option<Account> {
+    context.banks_client.get_account(*address).await.unwrap()
+}
+
+pub async fn token_balance(context: &mut ProgramTestContext, address: &Pubkey) -> u64 {
+    let account: Account = get_account(context, address)
+        .await
+        .expect("Token account not found");
+    spl_token::state::Account::unpack(&account.data)
+        .unwrap()
+        .amount
+}
+
+pub async fn get_gso_state(context: &mut ProgramTestContext, address: &Pubkey) -> GSOState {
+    let account: Account = get_account(context, address)
+        .await
+        .expect("GSOState not found");
+    GSOState::try_deserialize(&mut account.data.as_slice()).unwrap()
+}
+
+pub async fn get_project(context: &mut ProgramTestContext, project_name: &str) -> Project {
+    let account: Account = get_account(context, &find_project_address(project_name).0)
+        .await
+        .expect("Project not found");
+    Project::try_deserialize(&mut account.data.as_slice()).unwrap()
+}
+
+pub async fn now(context: &mut ProgramTestContext) -> u64 {
+    let clock: Clock = context.banks_client.get_sysvar().await.unwrap();
+    clock.unix_timestamp as u64
+}
+
+// Moves to a later slot so that retried transactions get a new blockhash and
+// sets the clock the programs see.
+pub async fn warp_to_timestamp(context: &mut ProgramTestContext, unix_timestamp: u64) {
+    let clock: Clock = context.banks_client.get_sysvar().await.unwrap();
+    context.warp_to_slot(clock.slot + 10).unwrap();
+    let mut clock: Clock = context.banks_client.get_sysvar().await.unwrap();
+    clock.unix_timestamp = unix_timestamp as i64;
+    context.set_sysvar(&clock);
+}
+
+// Mints and token accounts for a GSO, with NUM_TOKENS of the SO base mint in
+// the authority's base account for config to move into SO.
+pub async fn create_config_accounts(context: &mut ProgramTestContext) -> ConfigAccounts {
+    create_config_accounts_with_decimals(context, 6).await
+}
+
+pub async fn create_config_accounts_with_decimals(
+    context: &mut ProgramTestContext,
+    so_base_decimals: u8,
+) -> ConfigAccounts {
+    let so_base_mint: Pubkey = create_mint(context, so_base_decimals).await;
+    let so_quote_mint: Pubkey = create_mint(context, 6).await;
+    let so_base_account: Pubkey = create_associated_token_account(context, &so_base_mint).await;
+    let so_quote_account: Pubkey = create_associated_token_account(context, &so_quote_mint).await;
+    mint_to(context, &so_base_mint, &so_base_account, NUM_TOKENS).await;
+    ConfigAccounts {
+        authority: context.payer.pubkey(),
+        so_base_account,
+        so_quote_account,
+        so_base_mint,
+        so_quote_mint,
+    }
+}
+
+// Config args that lock until the subscription period ends and leave every
+// optional feature off.
+pub fn config_args(project_name: &str, subscription_period_end: u64) -> gso::instruction::Config {
+    gso::instruction::Config {
+        period_num: 1,
+        lockup_ratio_tokens_per_million: LOCKUP_RATIO_TOKENS_PER_MILLION,
+        lockup_period_end: subscription_period_end,
+        option_expiration: subscription_period_end,
+        subscription_period_end,
+        lot_size: LOT_SIZE,
+        num_tokens: NUM_TOKENS,
+        project_name: project_name.to_string(),
+        strike_price: STRIKE,
+        so_authority_bump: 0,
+        options: ConfigOptions::default(),
+    }
+}
+
+pub fn config_v2_args(args: gso::instruction::Config) -> gso::instruction::ConfigV2 {
+    gso::instruction::ConfigV2 {
+        period_num: args.period_num,
+        lockup_ratio_tokens_per_million: args.lockup_ratio_tokens_per_million,
+        lockup_period_end: args.lockup_period_end,
+        option_expiration: args.option_expiration,
+        subscription_period_end: args.subscription_period_end,
+        lot_size: args.lot_size,
+        num_tokens: args.num_tokens,
+        project_name: args.project_name,
+        strike_price: args.strike_price,
+        so_authority_bump: args.so_authority_bump,
+        options: args.options,
+    }
+}
+
+pub fn init_project(authority: &Pubkey, project_name: &str) -> Instruction {
+    instruction::init_project(
+        authority,
+        project_name,
+        0,
+        LOCKUP_RATIO_TOKENS_PER_MILLION,
+        LOT_SIZE,
+        STRIKE,
+    )
+}
+
+// Creates the project and configures period 1 of it, returning the state.
+pub async fn configure(
+    context: &mut ProgramTestContext,
+    accounts: &ConfigAccounts,
+    args: gso::instruction::Config,
+) -> GSOState {
+    let address: Pubkey = find_gso_state_address(args.period_num, &args.project_name).0;
+    process(
+        context,
+        &[
+            init_project(&accounts.authority, &args.project_name),
+            instruction::config(accounts, args),
+        ],
+        &[],
+    )
+    .await
+    .unwrap();
+    get_gso_state(context, &address).await
+}
+
+// Same as configure but with config_v2 and a separate lockup mint.
+pub async fn configure_v2(
+    context: &mut ProgramTestContext,
+    accounts: &ConfigAccounts,
+    lockup_mint: &Pubkey,
+    args: gso::instruction::Config,
+) -> GSOState {
+    let address: Pubkey = find_gso_state_address(args.period_num, &args.project_name).0;
+    process(
+        context,
+        &[
+            init_project(&accounts.authority, &args.project_name),
+            instruction::config_v2(accounts, lockup_mint, config_v2_args(args)),
+        ],
+        &[],
+    )
+    .await
+    .unwrap();
+    get_gso_state(context, &address).await
+}
+
+// Creates the option and xToken accounts and stakes from a new token account
+// holding amount of the lockup mint. Returns that account.
+pub async fn stake(
+    context: &mut ProgramTestContext,
+    state: &GSOState,
+    lockup_mint: &Pubkey,
+    amount: u64,
+) -> Pubkey {
+    let gso_state: Pubkey = find_gso_state_address(state.period_num, &state.project_name).0;
+    let user_base_account: Pubkey = create_token_account(context, lockup_mint).await;
+    mint_to(context, lockup_mint, &user_base_account, amount).await;
+    let so_option_mint: Pubkey =
+        find_so_option_mint_address(&state.staking_options_state, state.strike).0;
+    create_associated_token_account(context, &so_option_mint).await;
+    create_associated_token_account(context, &find_x_base_mint_address(&gso_state, 0).0).await;
+    let authority: Pubkey = context.payer.pubkey();
+    process(
+        context,
+        &[instruction::stake(
+            state,
+            &authority,
+            &user_base_account,
+            amount,
+            0,
+            0,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    user_base_account
+}
+
+pub async fn unstake(
+    context: &mut ProgramTestContext,
+    state: &GSOState,
+    user_base_account: &Pubkey,
+    amount: u64,
+) -> TransportResult<()> {
+    let authority: Pubkey = context.payer.pubkey();
+    process(
+        context,
+        &[instruction::unstake(
+            state,
+            &authority,
+            user_base_account,
+            amount,
+            0,
+        )],
+        &[],
+    )
+    .await
+}
@@ -0,0 +1,290 @@
+use std::cell::RefCell;
+use std::convert::TryFrom;
+use std::mem::transmute;
+use std::sync::Once;
+
+use solana_program_runtime::{stable_log, timings::ExecuteTimings};
+use solana_program_test::{builtin_process_instruction, InvokeContext};
+use solana_sdk::account::{ReadableAccount, WritableAccount};
+use solana_sdk::account_info::AccountInfo;
+use solana_sdk::entrypoint::{ProcessInstruction, ProgramResult};
+use solana_sdk::instruction::{Instruction, InstructionError};
+use solana_sdk::message::{Message, SanitizedMessage};
+use solana_sdk::program_error::ProgramError;
+use solana_sdk::program_stubs::{set_syscall_stubs, SyscallStubs};
+use solana_sdk::pubkey::Pubkey;
+
+// GSO, SO and Metaplex all create accounts through CPIs into the system
+// program. The CPI stubs in solana-program-test 1.9 can not pass a resized
+// account back to a native caller, so these stubs replace them. They are the
+// same as the solana-program-test stubs except that the caller's account data
+// is swapped out when the callee changed its length.
+
+thread_local! {
+    static INVOKE_CONTEXT: RefCell<Option<usize>> = const { RefCell::new(None) };
+    static PROCESSORS: RefCell<Vec<ProcessInstruction>> = const { RefCell::new(Vec::new()) };
+    // Buffers handed to callers whose account was resized, as (address, len).
+    static RESIZED: RefCell<Vec<(usize, usize)>> = const { RefCell::new(Vec::new()) };
+}
+
+fn set_invoke_context(invoke_context: &mut InvokeContext) {
+    INVOKE_CONTEXT.with(|cell| {
+        cell.replace(Some(invoke_context as *mut InvokeContext as usize));
+    });
+}
+
+fn get_invoke_context<'a, 'b>() -> &'a mut InvokeContext<'b> {
+    let address: usize = INVOKE_CONTEXT.with(|cell| cell.borrow().expect("Invoke context not set"));
+    unsafe { &mut *(address as *mut InvokeContext) }
+}
+
+// Same as solana_program_test::builtin_process_instruction but keeps the
+// invoke context for the stubs.
+pub fn process_instruction(
+    process_instruction: ProcessInstruction,
+    first_instruction_account: usize,
+    input: &[u8],
+    invoke_context: &mut InvokeContext,
+) -> Result<(), InstructionError> {
+    set_invoke_context(invoke_context);
+    PROCESSORS.with(|processors| processors.borrow_mut().push(process_instruction));
+    let result = builtin_process_instruction(
+        process_with_full_data,
+        first_instruction_account,
+        input,
+        invoke_context,
+    );
+    PROCESSORS.with(|processors| processors.borrow_mut().pop());
+    result
+}
+
+// Borsh serializes into a `&mut &mut [u8]`, which advances the slice in the
+// AccountInfo past what was written. On chain the runtime ignores that, but
+// solana-program-test commits whatever the slice points at, so Metaplex
+// metadata would come back truncated. Point the slices back at the whole
+// account before they are committed.
+fn process_with_full_data(
+    program_id: &Pubkey,
+    account_infos: &[AccountInfo],
+    input: &[u8],
+) -> ProgramResult {
+    let process_instruction: ProcessInstruction =
+        PROCESSORS.with(|processors| *processors.borrow().last().unwrap());
+    let mut buffers: Vec<(usize, usize)> = account_infos
+        .iter()
+        .map(|account_info| {
+            let data = account_info.data.borrow();
+            (data.as_ptr() as usize, data.len())
+        })
+        .collect();
+
+    let result = process_instruction(program_id, account_infos, input);
+
+    RESIZED.with(|resized| buffers.extend(resized.borrow().iter()));
+    for account_info in account_infos {
+        let mut data = account_info.data.borrow_mut();
+        let (address, len) = (data.as_ptr() as usize, data.len());
+        // An advanced slice still ends where its buffer does.
+        let buffer = buffers
+            .iter()
+            .rev()
+            .find(|(start, buffer_len)| *start < address && address + len == start + buffer_len);
+        if let Some(&(start, buffer_len)) = buffer {
+            *data = unsafe { std::slice::from_raw_parts_mut(start as *mut u8, buffer_len) };
+        }
+    }
+    result
+}
+
+// Same as solana_program_test::processor but with the resizing stubs.
+#[macro_export]
+macro_rules! native_processor {
+    ($process_instruction:expr) => {
+        Some(
+            |first_instruction_account: usize,
+             input: &[u8],
+             invoke_context: &mut solana_program_test::InvokeContext| {
+                $crate::common::native::process_instruction(
+                    $process_instruction,
+                    first_instruction_account,
+                    input,
+                    invoke_context,
+                )
+            },
+        )
+    };
+}
+
+// Replaces the stubs installed by the first ProgramTest, so call it after
+// starting one.
+pub fn install_syscall_stubs() {
+    static ONCE: Once = Once::new();
+    ONCE.call_once(|| {
+        let program_test_stubs: Box<dyn SyscallStubs> = set_syscall_stubs(Box::new(Unset {}));
+        set_syscall_stubs(Box::new(ResizingStubs {
+            inner: program_test_stubs,
+        }));
+    });
+}
+
+struct Unset {}
+impl SyscallStubs for Unset {}
+
+struct ResizingStubs {
+    inner: Box<dyn SyscallStubs>,
+}
+
+impl SyscallStubs for ResizingStubs {
+    fn sol_log(&self, message: &str) {
+        self.inner.sol_log(message)
+    }
+
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        let invoke_context = get_invoke_context();
+        let log_collector = invoke_context.get_log_collector();
+
+        let caller: Pubkey = *invoke_context.get_caller().expect("get_caller");
+        let message = Message::new(std::slice::from_ref(instruction), None);
+        let program_id_index: usize = message.instructions[0].program_id_index as usize;
+        let program_id: Pubkey = message.account_keys[program_id_index];
+        let caller_privileges: Vec<bool> = (0..message.account_keys.len())
+            .map(|index| message.is_writable(index))
+            .collect();
+
+        stable_log::program_invoke(
+            &log_collector,
+            &program_id,
+            invoke_context.get_stack_height(),
+        );
+
+        // Move the caller's view of the accounts into the runtime. Anchor does
+        // not pass the system program to its own CPIs, so the program may be
+        // missing from the account infos.
+        let mut account_indices = Vec::with_capacity(message.account_keys.len());
+        let mut accounts = Vec::with_capacity(message.account_keys.len());
+        for (index, account_key) in message.account_keys.iter().enumerate() {
+            let (account_index, account) = invoke_context
+                .get_account(account_key)
+                .ok_or(InstructionError::MissingAccount)
+                .unwrap();
+            let account_info: Option<&AccountInfo> = account_infos
+                .iter()
+                .find(|account_info| account_info.unsigned_key() == account_key);
+            match account_info {
+                Some(account_info) => {
+                    let mut account = account.borrow_mut();
+                    account.copy_into_owner_from_slice(account_info.owner.as_ref());
+                    account.set_data_from_slice(&account_info.try_borrow_data().unwrap());
+                    account.set_lamports(account_info.lamports());
+                    account.set_executable(account_info.executable);
+                    account.set_rent_epoch(account_info.rent_epoch);
+                }
+                None => assert_eq!(*account_key, program_id, "Missing account {}", account_key),
+            }
+            let account_info = if message.is_writable(index) {
+                account_info
+            } else {
+                None
+            };
+            account_indices.push(account_index);
+            accounts.push((account, account_info));
+        }
+        let (program_account_index, _program_account) =
+            invoke_context.get_account(&program_id).unwrap();
+        let program_indices: Vec<usize> = vec![program_account_index];
+
+        for account_info in account_infos {
+            for instruction_account in &instruction.accounts {
+                if *account_info.unsigned_key() == instruction_account.pubkey
+                    && instruction_account.is_signer
+                    && !account_info.is_signer
+                {
+                    let program_signer: bool = signers_seeds.iter().any(|seeds| {
+                        Pubkey::create_program_address(seeds, &caller).unwrap()
+                            == instruction_account.pubkey
+                    });
+                    assert!(
+                        program_signer,
+                        "Missing signer for {}",
+                        instruction_account.pubkey
+                    );
+                }
+            }
+        }
+
+        invoke_context.record_instruction(invoke_context.get_stack_height(), instruction.clone());
+
+        let message = SanitizedMessage::Legacy(message);
+        invoke_context
+            .process_instruction(
+                &message,
+                &message.instructions()[0],
+                &program_indices,
+                &account_indices,
+                &caller_privileges,
+                &mut ExecuteTimings::default(),
+            )
+            .result
+            .map_err(|err| ProgramError::try_from(err).unwrap_or_else(|err| panic!("{}", err)))?;
+
+        // Move the writable accounts back into the caller's view.
+        for (account, account_info) in accounts.iter() {
+            if let Some(account_info) = account_info {
+                let account = account.borrow();
+                **account_info.try_borrow_mut_lamports().unwrap() = account.lamports();
+                if account_info.owner != account.owner() {
+                    #[allow(mutable_transmutes)]
+                    let owner: &mut Pubkey =
+                        unsafe { transmute::<&Pubkey, &mut Pubkey>(account_info.owner) };
+                    *owner = *account.owner();
+                }
+                let mut data = account_info.try_borrow_mut_data()?;
+                if data.len() == account.data().len() {
+                    data.clone_from_slice(account.data());
+                } else {
+                    // The caller's data is a slice of the account it was
+                    // given, so it gets a new buffer. Leaking is fine for
+                    // the lifetime of a test.
+                    *data = Box::leak(account.data().to_vec().into_boxed_slice());
+                    RESIZED.with(|resized| {
+                        resized
+                            .borrow_mut()
+                            .push((data.as_ptr() as usize, data.len()))
+                    });
+                }
+            }
+        }
+
+        stable_log::program_success(&log_collector, &program_id);
+        Ok(())
+    }
+
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.inner.sol_get_clock_sysvar(var_addr)
+    }
+
+    fn sol_get_epoch_schedule_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.inner.sol_get_epoch_schedule_sysvar(var_addr)
+    }
+
+    fn sol_get_fees_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.inner.sol_get_fees_sysvar(var_addr)
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.inner.sol_get_rent_sysvar(var_addr)
+    }
+
+    fn sol_get_return_data(&self) -> Option<(Pubkey, Vec<u8>)> {
+        self.inner.sol_get_return_data()
+    }
+
+    fn sol_set_return_data(&self, data: &[u8]) {
+        self.inner.sol_set_return_data(data)
+    }
+}
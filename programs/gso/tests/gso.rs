@@ -0,0 +1,1212 @@
+// Native solana-program-test suite for GSO. Runs with cargo test and needs no
+// network, unlike tests/gso.ts which clones SO and Metaplex from devnet.
+
+use anchor_lang::{AccountDeserialize, AccountSerialize};
+use mpl_token_metadata::state::{Metadata, TokenMetadataAccount};
+use solana_program_test::*;
+use solana_sdk::account::AccountSharedData;
+use solana_sdk::program_pack::Pack;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::system_instruction;
+
+use gso::{DepositRecord, ErrorCode, GSOState, Project};
+use gso_client::*;
+
+mod common;
+
+use crate::common::*;
+
+#[tokio::test]
+async fn test_config() {
+    let mut context = common::start().await;
+    let accounts = create_config_accounts(&mut context).await;
+    let subscription_period_end: u64 = now(&mut context).await + SUBSCRIPTION_SECONDS;
+    let state: GSOState = configure(
+        &mut context,
+        &accounts,
+        config_args("CONFIG", subscription_period_end),
+    )
+    .await;
+
+    assert_eq!(state.project_name, "CONFIG");
+    assert_eq!(state.period_num, 1);
+    assert_eq!(state.strike, STRIKE);
+    assert_eq!(state.option_base_mint, accounts.so_base_mint);
+    assert_eq!(state.lockup_mint, accounts.so_base_mint);
+    assert_eq!(state.lockup_period_end, subscription_period_end);
+    assert_eq!(
+        state.staking_options_state,
+        find_so_state_address(&so_name("CONFIG", 1), &accounts.so_base_mint).0
+    );
+
+    // Config moves the tokens for the options into SO.
+    assert_eq!(
+        token_balance(&mut context, &accounts.so_base_account).await,
+        0
+    );
+    assert_eq!(
+        token_balance(
+            &mut context,
+            &find_so_base_vault_address(&so_name("CONFIG", 1), &accounts.so_base_mint).0
+        )
+        .await,
+        NUM_TOKENS
+    );
+
+    let project = get_account(&mut context, &find_project_address("CONFIG").0)
+        .await
+        .unwrap();
+    let project: Project =
+        anchor_lang::AccountDeserialize::try_deserialize(&mut project.data.as_slice()).unwrap();
+    assert_eq!(project.latest_period, 1);
+}
+
+#[tokio::test]
+async fn test_init_project_for_legacy_gso() {
+    let mut context = common::start().await;
+    let accounts = create_config_accounts(&mut context).await;
+    let subscription_period_end: u64 = now(&mut context).await + SUBSCRIPTION_SECONDS;
+    configure(
+        &mut context,
+        &accounts,
+        config_args("LEGACY", subscription_period_end),
+    )
+    .await;
+
+    // Period 1 was configured before the project existed.
+    context.set_account(
+        &find_project_address("LEGACY").0,
+        &AccountSharedData::default(),
+    );
+
+    let other: Keypair = Keypair::new();
+    let payer: Pubkey = context.payer.pubkey();
+    process(
+        &mut context,
+        &[system_instruction::transfer(
+            &payer,
+            &other.pubkey(),
+            1_000_000_000,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+
+    // Someone else can neither start the name over nor continue it.
+    let result = process(
+        &mut context,
+        &[instruction::init_project(
+            &other.pubkey(),
+            "LEGACY",
+            0,
+            LOCKUP_RATIO_TOKENS_PER_MILLION,
+            LOT_SIZE,
+            STRIKE,
+        )],
+        &[&other],
+    )
+    .await;
+    assert_gso_error(result, ErrorCode::InvalidPeriodNum);
+    let result = process(
+        &mut context,
+        &[instruction::init_project(
+            &other.pubkey(),
+            "LEGACY",
+            1,
+            LOCKUP_RATIO_TOKENS_PER_MILLION,
+            LOT_SIZE,
+            STRIKE,
+        )],
+        &[&other],
+    )
+    .await;
+    assert_gso_error(result, ErrorCode::ProjectNameTaken);
+
+    // The GSO authority continues after its latest period.
+    process(
+        &mut context,
+        &[instruction::init_project(
+            &payer,
+            "LEGACY",
+            1,
+            LOCKUP_RATIO_TOKENS_PER_MILLION,
+            LOT_SIZE,
+            STRIKE,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    let project: Project = get_project(&mut context, "LEGACY").await;
+    assert_eq!(project.latest_period, 1);
+    assert_eq!(project.authority, payer);
+
+    // And can hand the project over.
+    process(
+        &mut context,
+        &[
+            instruction::propose_project_authority("LEGACY", &payer, &other.pubkey()),
+            instruction::accept_project_authority("LEGACY", &other.pubkey()),
+        ],
+        &[&other],
+    )
+    .await
+    .unwrap();
+    let project: Project = get_project(&mut context, "LEGACY").await;
+    assert_eq!(project.authority, other.pubkey());
+    assert_eq!(project.pending_authority, Pubkey::default());
+}
+
+#[tokio::test]
+async fn test_init_project_takes_back_legacy_gso() {
+    let mut context = common::start().await;
+    let accounts = create_config_accounts(&mut context).await;
+    let subscription_period_end: u64 = now(&mut context).await + SUBSCRIPTION_SECONDS;
+    configure(
+        &mut context,
+        &accounts,
+        config_args("LATE", subscription_period_end),
+    )
+    .await;
+    let now_ts: u64 = now(&mut context).await;
+    warp_to_timestamp(&mut context, now_ts).await;
+    mint_to(
+        &mut context,
+        &accounts.so_base_mint,
+        &accounts.so_base_account,
+        NUM_TOKENS,
+    )
+    .await;
+    let mut args = config_args("LATE", subscription_period_end);
+    args.period_num = 2;
+    process(&mut context, &[instruction::config(&accounts, args)], &[])
+        .await
+        .unwrap();
+
+    // Only period 2 was configured, before projects existed.
+    let payer: Pubkey = context.payer.pubkey();
+    let address: Pubkey = find_gso_state_address(2, "LATE").0;
+    context.set_account(
+        &find_project_address("LATE").0,
+        &AccountSharedData::default(),
+    );
+    context.set_account(
+        &find_gso_state_address(1, "LATE").0,
+        &AccountSharedData::default(),
+    );
+
+    // Someone else can claim the name since the legacy GSO is not at period 0
+    // or 1.
+    let other: Keypair = Keypair::new();
+    process(
+        &mut context,
+        &[system_instruction::transfer(
+            &payer,
+            &other.pubkey(),
+            1_000_000_000,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    process(
+        &mut context,
+        &[instruction::init_project(
+            &other.pubkey(),
+            "LATE",
+            0,
+            LOCKUP_RATIO_TOKENS_PER_MILLION,
+            LOT_SIZE,
+            STRIKE,
+        )],
+        &[&other],
+    )
+    .await
+    .unwrap();
+
+    // A GSO configured under a project does not prove the name was used first.
+    let take_back = instruction::init_project(
+        &payer,
+        "LATE",
+        2,
+        LOCKUP_RATIO_TOKENS_PER_MILLION,
+        LOT_SIZE,
+        STRIKE,
+    );
+    let result = process(&mut context, std::slice::from_ref(&take_back), &[]).await;
+    assert_gso_error(result, ErrorCode::ProjectNameTaken);
+
+    let mut state: GSOState = get_gso_state(&mut context, &address).await;
+    state.so_name = String::new();
+    let mut account = get_account(&mut context, &address).await.unwrap();
+    let mut data: Vec<u8> = Vec::new();
+    state.try_serialize(&mut data).unwrap();
+    account.data.fill(0);
+    account.data[..data.len()].copy_from_slice(&data);
+    context.set_account(&address, &AccountSharedData::from(account));
+
+    // The legacy GSO authority takes the project back.
+    let now_ts: u64 = now(&mut context).await;
+    warp_to_timestamp(&mut context, now_ts).await;
+    process(&mut context, &[take_back], &[]).await.unwrap();
+    let project: Project = get_project(&mut context, "LATE").await;
+    assert_eq!(project.authority, payer);
+    assert_eq!(project.latest_period, 2);
+
+    // And the name can not be claimed back from under it.
+    let result = process(
+        &mut context,
+        &[instruction::init_project(
+            &other.pubkey(),
+            "LATE",
+            0,
+            LOCKUP_RATIO_TOKENS_PER_MILLION,
+            LOT_SIZE,
+            STRIKE,
+        )],
+        &[&other],
+    )
+    .await;
+    assert_gso_error(result, ErrorCode::InvalidPeriodNum);
+}
+
+#[tokio::test]
+async fn test_config_lockup_end_before_subscription_end_fails() {
+    let mut context = common::start().await;
+    let accounts = create_config_accounts(&mut context).await;
+    let subscription_period_end: u64 = now(&mut context).await + SUBSCRIPTION_SECONDS;
+    let mut args = config_args("BADLOCKUP", subscription_period_end);
+    args.lockup_period_end = subscription_period_end - 1;
+
+    let result = process(
+        &mut context,
+        &[
+            init_project(&accounts.authority, "BADLOCKUP"),
+            instruction::config(&accounts, args),
+        ],
+        &[],
+    )
+    .await;
+    assert_gso_error(result, ErrorCode::InvalidLockupEnd);
+}
+
+#[tokio::test]
+async fn test_config_option_expiration_before_subscription_end_fails() {
+    let mut context = common::start().await;
+    let accounts = create_config_accounts(&mut context).await;
+    let subscription_period_end: u64 = now(&mut context).await + SUBSCRIPTION_SECONDS;
+    let mut args = config_args("BADEXPIRY", subscription_period_end);
+    args.option_expiration = subscription_period_end - 1;
+
+    let result = process(
+        &mut context,
+        &[
+            init_project(&accounts.authority, "BADEXPIRY"),
+            instruction::config(&accounts, args),
+        ],
+        &[],
+    )
+    .await;
+    assert_gso_error(result, ErrorCode::InvalidOptionExpiration);
+}
+
+#[tokio::test]
+async fn test_config_v2() {
+    let mut context = common::start().await;
+    let accounts = create_config_accounts(&mut context).await;
+    // Different decimals from the SO base mint to check the xToken decimals.
+    let lockup_mint: Pubkey = create_mint(&mut context, 9).await;
+    let subscription_period_end: u64 = now(&mut context).await + SUBSCRIPTION_SECONDS;
+    let state: GSOState = configure_v2(
+        &mut context,
+        &accounts,
+        &lockup_mint,
+        config_args("CONFIGV2", subscription_period_end),
+    )
+    .await;
+    let address: Pubkey = find_gso_state_address(1, "CONFIGV2").0;
+    assert_eq!(state.option_base_mint, accounts.so_base_mint);
+    assert_eq!(state.lockup_mint, lockup_mint);
+    let x_base_mint = get_account(&mut context, &find_x_base_mint_address(&address, 0).0)
+        .await
+        .unwrap();
+    let x_base_mint = spl_token::state::Mint::unpack(&x_base_mint.data).unwrap();
+    assert_eq!(x_base_mint.decimals, 9);
+
+    // Stakes are in the lockup mint, options are still for the SO base mint.
+    let user_base_account: Pubkey = stake(&mut context, &state, &lockup_mint, NUM_STAKE).await;
+    assert_eq!(token_balance(&mut context, &user_base_account).await, 0);
+    let base_vault: Pubkey = find_base_vault_address(&address, 0).0;
+    assert_eq!(token_balance(&mut context, &base_vault).await, NUM_STAKE);
+    let base_vault = get_account(&mut context, &base_vault).await.unwrap();
+    let base_vault = spl_token::state::Account::unpack(&base_vault.data).unwrap();
+    assert_eq!(base_vault.mint, lockup_mint);
+    // The ratio is in whole tokens, so 9 decimal lockup atoms earn a
+    // thousandth as many 6 decimal option base atoms.
+    assert_eq!(
+        token_balance(
+            &mut context,
+            &so_user_option_account(&accounts.authority, &state)
+        )
+        .await,
+        NUM_STAKE * LOCKUP_RATIO_TOKENS_PER_MILLION / 1_000_000 / 1_000
+    );
+}
+
+#[tokio::test]
+async fn test_config_v2_more_option_decimals() {
+    let mut context = common::start().await;
+    let accounts = create_config_accounts_with_decimals(&mut context, 9).await;
+    let lockup_mint: Pubkey = create_mint(&mut context, 6).await;
+    let subscription_period_end: u64 = now(&mut context).await + SUBSCRIPTION_SECONDS;
+    let state: GSOState = configure_v2(
+        &mut context,
+        &accounts,
+        &lockup_mint,
+        config_args("DECIMALS", subscription_period_end),
+    )
+    .await;
+    assert_eq!(state.lockup_decimals, 6);
+    assert_eq!(state.option_base_decimals, 9);
+
+    // 0.001 of the lockup mint earns 0.0002 of the option base mint.
+    let amount: u64 = 1_000;
+    let user_base_account: Pubkey = stake(&mut context, &state, &lockup_mint, amount).await;
+    assert_eq!(token_balance(&mut context, &user_base_account).await, 0);
+    let address: Pubkey = find_gso_state_address(1, "DECIMALS").0;
+    assert_eq!(
+        token_balance(&mut context, &find_base_vault_address(&address, 0).0).await,
+        amount
+    );
+    assert_eq!(
+        token_balance(
+            &mut context,
+            &so_user_option_account(&accounts.authority, &state)
+        )
+        .await,
+        200_000
+    );
+}
+
+#[tokio::test]
+async fn test_config_v2_rational_lockup_ratio() {
+    let mut context = common::start().await;
+    let accounts = create_config_accounts(&mut context).await;
+    let lockup_mint: Pubkey = create_mint(&mut context, 6).await;
+    let subscription_period_end: u64 = now(&mut context).await + SUBSCRIPTION_SECONDS;
+    let mut args = config_args("THIRD", subscription_period_end);
+    args.options.lockup_ratio_num = 1;
+    args.options.lockup_ratio_den = 3;
+    let state: GSOState = configure_v2(&mut context, &accounts, &lockup_mint, args).await;
+    assert_eq!(state.lockup_ratio_num, 1);
+    assert_eq!(state.lockup_ratio_den, 3);
+
+    // A third per token in millionths would be 333_333 and earn 999_999.
+    let amount: u64 = 3 * NUM_TOKENS;
+    stake(&mut context, &state, &lockup_mint, amount).await;
+    assert_eq!(
+        token_balance(
+            &mut context,
+            &so_user_option_account(&accounts.authority, &state)
+        )
+        .await,
+        NUM_TOKENS
+    );
+}
+
+#[tokio::test]
+async fn test_stake_wrong_mint_fails() {
+    let mut context = common::start().await;
+    let accounts = create_config_accounts(&mut context).await;
+    let lockup_mint: Pubkey = create_mint(&mut context, 6).await;
+    let subscription_period_end: u64 = now(&mut context).await + SUBSCRIPTION_SECONDS;
+    let state: GSOState = configure_v2(
+        &mut context,
+        &accounts,
+        &lockup_mint,
+        config_args("WRONGMINT", subscription_period_end),
+    )
+    .await;
+
+    // Staking the SO base mint into a GSO that locks another mint.
+    let address: Pubkey = find_gso_state_address(1, "WRONGMINT").0;
+    create_associated_token_account(&mut context, &find_x_base_mint_address(&address, 0).0).await;
+    create_associated_token_account(
+        &mut context,
+        &find_so_option_mint_address(&state.staking_options_state, state.strike).0,
+    )
+    .await;
+    let authority: Pubkey = context.payer.pubkey();
+    let result = process(
+        &mut context,
+        &[instruction::stake(
+            &state,
+            &authority,
+            &accounts.so_base_account,
+            NUM_STAKE,
+            0,
+            0,
+        )],
+        &[],
+    )
+    .await;
+    assert_gso_error(result, ErrorCode::InvalidTokenMint);
+
+    // Unstaking into it is rejected the same way.
+    let result = unstake(&mut context, &state, &accounts.so_base_account, NUM_STAKE).await;
+    assert_gso_error(result, ErrorCode::InvalidTokenMint);
+}
+
+#[tokio::test]
+async fn test_migrate_lockup_mint() {
+    let mut context = common::start().await;
+    let accounts = create_config_accounts(&mut context).await;
+    let lockup_mint: Pubkey = create_mint(&mut context, 6).await;
+    let subscription_period_end: u64 = now(&mut context).await + SUBSCRIPTION_SECONDS;
+    let state: GSOState = configure_v2(
+        &mut context,
+        &accounts,
+        &lockup_mint,
+        config_args("MIGRATE", subscription_period_end),
+    )
+    .await;
+
+    // A state from before lockup_mint was stored has zeros there.
+    let address: Pubkey = find_gso_state_address(1, "MIGRATE").0;
+    let mut account = get_account(&mut context, &address).await.unwrap();
+    let mut old_state: GSOState = state.clone();
+    old_state.lockup_mint = Pubkey::default();
+    let mut data: Vec<u8> = Vec::new();
+    anchor_lang::AccountSerialize::try_serialize(&old_state, &mut data).unwrap();
+    account.data[..data.len()].copy_from_slice(&data);
+    context.set_account(&address, &account.into());
+
+    process(
+        &mut context,
+        &[instruction::migrate_lockup_mint(&state)],
+        &[],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        get_gso_state(&mut context, &address).await.lockup_mint,
+        lockup_mint
+    );
+
+    // Same transaction again, so it needs a new blockhash.
+    let now_ts: u64 = now(&mut context).await;
+    warp_to_timestamp(&mut context, now_ts).await;
+    let result = process(
+        &mut context,
+        &[instruction::migrate_lockup_mint(&state)],
+        &[],
+    )
+    .await;
+    assert_gso_error(result, ErrorCode::AlreadyMigrated);
+}
+
+#[tokio::test]
+async fn test_stake() {
+    let mut context = common::start().await;
+    let accounts = create_config_accounts(&mut context).await;
+    let subscription_period_end: u64 = now(&mut context).await + SUBSCRIPTION_SECONDS;
+    let state: GSOState = configure(
+        &mut context,
+        &accounts,
+        config_args("STAKE", subscription_period_end),
+    )
+    .await;
+
+    let user_base_account: Pubkey =
+        stake(&mut context, &state, &accounts.so_base_mint, NUM_STAKE).await;
+
+    let address: Pubkey = find_gso_state_address(1, "STAKE").0;
+    assert_eq!(token_balance(&mut context, &user_base_account).await, 0);
+    assert_eq!(
+        token_balance(&mut context, &find_base_vault_address(&address, 0).0).await,
+        NUM_STAKE
+    );
+    assert_eq!(
+        token_balance(
+            &mut context,
+            &user_x_base_account(&accounts.authority, &address)
+        )
+        .await,
+        NUM_STAKE
+    );
+    assert_eq!(
+        token_balance(
+            &mut context,
+            &so_user_option_account(&accounts.authority, &state)
+        )
+        .await,
+        NUM_STAKE * LOCKUP_RATIO_TOKENS_PER_MILLION / 1_000_000
+    );
+    assert_eq!(
+        get_gso_state(&mut context, &address).await.total_locked,
+        NUM_STAKE
+    );
+    // Without a per wallet cap there is nothing to track.
+    let deposit_record: Pubkey = find_deposit_record_address(&address, &accounts.authority).0;
+    assert!(get_account(&mut context, &deposit_record).await.is_none());
+}
+
+// Configures a GSO whose ratio decays from LOCKUP_RATIO_TOKENS_PER_MILLION to
+// half of it over the subscription period, stakes NUM_STAKE once elapsed
+// seconds have passed and returns the options issued.
+async fn stake_on_schedule(
+    project_name: &str,
+    lockup_ratio_schedule: u8,
+    lockup_ratio_steps: u64,
+    elapsed: u64,
+) -> u64 {
+    let mut context = common::start().await;
+    let accounts = create_config_accounts(&mut context).await;
+    let subscription_period_start: u64 = now(&mut context).await;
+    let mut args = config_args(
+        project_name,
+        subscription_period_start + SUBSCRIPTION_SECONDS,
+    );
+    args.options.subscription_period_start = subscription_period_start;
+    args.options.lockup_ratio_schedule = lockup_ratio_schedule;
+    args.options.lockup_ratio_end_tokens_per_million = LOCKUP_RATIO_TOKENS_PER_MILLION / 2;
+    args.options.lockup_ratio_steps = lockup_ratio_steps;
+    let state: GSOState = configure(&mut context, &accounts, args).await;
+
+    warp_to_timestamp(&mut context, subscription_period_start + elapsed).await;
+    stake(&mut context, &state, &accounts.so_base_mint, NUM_STAKE).await;
+    token_balance(
+        &mut context,
+        &so_user_option_account(&accounts.authority, &state),
+    )
+    .await
+}
+
+#[tokio::test]
+async fn test_stake_linear_schedule() {
+    // 30% of the way through, so 30% of the way from the start to end ratio.
+    let options_issued: u64 = stake_on_schedule("LINEAR", gso::RATIO_SCHEDULE_LINEAR, 0, 30).await;
+    assert_eq!(options_issued, NUM_STAKE * 170_000 / 1_000_000);
+}
+
+#[tokio::test]
+async fn test_stake_stepwise_schedule() {
+    // 45% of the way through 5 steps is the third step, which is half way
+    // from the start to end ratio.
+    let options_issued: u64 =
+        stake_on_schedule("STEPWISE", gso::RATIO_SCHEDULE_STEPWISE, 5, 45).await;
+    assert_eq!(options_issued, NUM_STAKE * 150_000 / 1_000_000);
+}
+
+#[tokio::test]
+async fn test_stake_over_wallet_cap_fails() {
+    let mut context = common::start().await;
+    let accounts = create_config_accounts(&mut context).await;
+    let subscription_period_end: u64 = now(&mut context).await + SUBSCRIPTION_SECONDS;
+    let mut args = config_args("CAP", subscription_period_end);
+    args.options.max_per_wallet = NUM_STAKE;
+    let state: GSOState = configure(&mut context, &accounts, args).await;
+    stake(&mut context, &state, &accounts.so_base_mint, NUM_STAKE).await;
+
+    let address: Pubkey = find_gso_state_address(1, "CAP").0;
+    let deposit_record: Pubkey = find_deposit_record_address(&address, &accounts.authority).0;
+    let account = get_account(&mut context, &deposit_record)
+        .await
+        .expect("DepositRecord not found");
+    let record = DepositRecord::try_deserialize(&mut account.data.as_slice()).unwrap();
+    assert_eq!(record.amount_deposited, NUM_STAKE);
+
+    let user_base_account: Pubkey =
+        create_token_account(&mut context, &accounts.so_base_mint).await;
+    mint_to(&mut context, &accounts.so_base_mint, &user_base_account, 10).await;
+    let result = process(
+        &mut context,
+        &[instruction::stake(
+            &state,
+            &accounts.authority,
+            &user_base_account,
+            10,
+            0,
+            0,
+        )],
+        &[],
+    )
+    .await;
+    assert_gso_error(result, ErrorCode::WalletCapExceeded);
+}
+
+#[tokio::test]
+async fn test_unstake_before_lockup_end_fails() {
+    let mut context = common::start().await;
+    let accounts = create_config_accounts(&mut context).await;
+    let subscription_period_end: u64 = now(&mut context).await + SUBSCRIPTION_SECONDS;
+    let state: GSOState = configure(
+        &mut context,
+        &accounts,
+        config_args("EARLY", subscription_period_end),
+    )
+    .await;
+    let user_base_account: Pubkey =
+        stake(&mut context, &state, &accounts.so_base_mint, NUM_STAKE).await;
+
+    let result = unstake(&mut context, &state, &user_base_account, NUM_STAKE).await;
+    assert_gso_error(result, ErrorCode::NotYetExpired);
+}
+
+#[tokio::test]
+async fn test_unstake_after_lockup_end() {
+    let mut context = common::start().await;
+    let accounts = create_config_accounts(&mut context).await;
+    let subscription_period_end: u64 = now(&mut context).await + SUBSCRIPTION_SECONDS;
+    let mut args = config_args("UNSTAKE", subscription_period_end);
+    args.lockup_period_end = subscription_period_end + SUBSCRIPTION_SECONDS;
+    args.option_expiration = args.lockup_period_end;
+    let state: GSOState = configure(&mut context, &accounts, args).await;
+    let user_base_account: Pubkey =
+        stake(&mut context, &state, &accounts.so_base_mint, NUM_STAKE).await;
+
+    // Between the subscription end and the lockup end is still locked.
+    warp_to_timestamp(&mut context, subscription_period_end + 1).await;
+    let result = unstake(&mut context, &state, &user_base_account, NUM_STAKE).await;
+    assert_gso_error(result, ErrorCode::NotYetExpired);
+
+    warp_to_timestamp(&mut context, state.lockup_period_end + 1).await;
+    unstake(&mut context, &state, &user_base_account, NUM_STAKE)
+        .await
+        .unwrap();
+
+    let address: Pubkey = find_gso_state_address(1, "UNSTAKE").0;
+    assert_eq!(
+        token_balance(&mut context, &user_base_account).await,
+        NUM_STAKE
+    );
+    assert_eq!(
+        token_balance(
+            &mut context,
+            &user_x_base_account(&accounts.authority, &address)
+        )
+        .await,
+        0
+    );
+    assert_eq!(
+        token_balance(&mut context, &find_base_vault_address(&address, 0).0).await,
+        0
+    );
+}
+
+#[tokio::test]
+async fn test_unstake_during_vesting() {
+    let mut context = common::start().await;
+    let accounts = create_config_accounts(&mut context).await;
+    let subscription_period_end: u64 = now(&mut context).await + SUBSCRIPTION_SECONDS;
+    let mut args = config_args("VESTING", subscription_period_end);
+    args.lockup_period_end = subscription_period_end + SUBSCRIPTION_SECONDS;
+    args.option_expiration = args.lockup_period_end;
+    args.options.vesting_end = args.lockup_period_end + 100;
+    let state: GSOState = configure(&mut context, &accounts, args).await;
+    let user_base_account: Pubkey =
+        stake(&mut context, &state, &accounts.so_base_mint, NUM_STAKE).await;
+
+    // Half way through vesting, so half of the stake is unlocked.
+    warp_to_timestamp(&mut context, state.lockup_period_end + 50).await;
+    unstake(&mut context, &state, &user_base_account, NUM_STAKE / 4)
+        .await
+        .unwrap();
+    assert_eq!(
+        token_balance(&mut context, &user_base_account).await,
+        NUM_STAKE / 4
+    );
+    let result = unstake(&mut context, &state, &user_base_account, NUM_STAKE / 2).await;
+    assert_gso_error(result, ErrorCode::NotYetVested);
+
+    // Same transaction as the first unstake, so it needs a new blockhash.
+    warp_to_timestamp(&mut context, state.lockup_period_end + 50).await;
+    unstake(&mut context, &state, &user_base_account, NUM_STAKE / 4)
+        .await
+        .unwrap();
+    assert_eq!(
+        token_balance(&mut context, &user_base_account).await,
+        NUM_STAKE / 2
+    );
+
+    // Everything is unlocked at the vesting end.
+    warp_to_timestamp(&mut context, state.vesting_end).await;
+    unstake(&mut context, &state, &user_base_account, NUM_STAKE / 2)
+        .await
+        .unwrap();
+    assert_eq!(
+        token_balance(&mut context, &user_base_account).await,
+        NUM_STAKE
+    );
+}
+
+#[tokio::test]
+async fn test_unstake_early_penalty_kept_in_vault() {
+    let mut context = common::start().await;
+    let accounts = create_config_accounts(&mut context).await;
+    let subscription_period_end: u64 = now(&mut context).await + SUBSCRIPTION_SECONDS;
+    let mut args = config_args("PENALTY", subscription_period_end);
+    args.lockup_period_end = subscription_period_end + SUBSCRIPTION_SECONDS;
+    args.option_expiration = args.lockup_period_end;
+    args.options.early_unstake_penalty_bps = 1_000;
+    let state: GSOState = configure(&mut context, &accounts, args).await;
+    let user_base_account: Pubkey =
+        stake(&mut context, &state, &accounts.so_base_mint, NUM_STAKE).await;
+
+    // The penalty would go to whoever stakes after it while still open.
+    let result = unstake(&mut context, &state, &user_base_account, NUM_STAKE / 2).await;
+    assert_gso_error(result, ErrorCode::PenaltyDuringSubscription);
+
+    warp_to_timestamp(&mut context, subscription_period_end + 1).await;
+    unstake(&mut context, &state, &user_base_account, NUM_STAKE / 2)
+        .await
+        .unwrap();
+    let penalty: u64 = NUM_STAKE / 2 / 10;
+    assert_eq!(
+        token_balance(&mut context, &user_base_account).await,
+        NUM_STAKE / 2 - penalty
+    );
+
+    // The remaining stake gets the penalty.
+    warp_to_timestamp(&mut context, state.lockup_period_end + 1).await;
+    unstake(&mut context, &state, &user_base_account, NUM_STAKE / 2)
+        .await
+        .unwrap();
+    assert_eq!(
+        token_balance(&mut context, &user_base_account).await,
+        NUM_STAKE
+    );
+}
+
+#[tokio::test]
+async fn test_early_unstake_after_lockup_end_fails() {
+    let mut context = common::start().await;
+    let accounts = create_config_accounts(&mut context).await;
+    let subscription_period_end: u64 = now(&mut context).await + SUBSCRIPTION_SECONDS;
+    let mut args = config_args("EARLY", subscription_period_end);
+    args.option_expiration = subscription_period_end + SUBSCRIPTION_SECONDS;
+    let state: GSOState = configure(&mut context, &accounts, args).await;
+    let user_base_account: Pubkey =
+        stake(&mut context, &state, &accounts.so_base_mint, NUM_STAKE).await;
+
+    // Handing back the options gets the tokens out before the lockup ends.
+    let authority: Pubkey = context.payer.pubkey();
+    process(
+        &mut context,
+        &[instruction::early_unstake(
+            &state,
+            &authority,
+            &user_base_account,
+            NUM_STAKE / 2,
+            0,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        token_balance(&mut context, &user_base_account).await,
+        NUM_STAKE / 2
+    );
+
+    // The options are still live, but the lockup is over so vesting applies.
+    warp_to_timestamp(&mut context, subscription_period_end + 1).await;
+    let result = process(
+        &mut context,
+        &[instruction::early_unstake(
+            &state,
+            &authority,
+            &user_base_account,
+            NUM_STAKE / 2,
+            0,
+        )],
+        &[],
+    )
+    .await;
+    assert_gso_error(result, ErrorCode::LockupEnded);
+}
+
+#[tokio::test]
+async fn test_slash_then_unstake_pro_rata() {
+    let mut context = common::start().await;
+    let accounts = create_config_accounts(&mut context).await;
+    let subscription_period_end: u64 = now(&mut context).await + SUBSCRIPTION_SECONDS;
+    let mut args = config_args("SLASH", subscription_period_end);
+    args.lockup_period_end = subscription_period_end + 2 * gso::MIN_SLASH_TIMELOCK;
+    args.option_expiration = args.lockup_period_end;
+    // Up to 10% can be slashed.
+    args.options.max_slash_bps = 1_000;
+    args.options.slash_timelock = gso::MIN_SLASH_TIMELOCK;
+    args.options.slash_destination = accounts.so_base_account;
+    let state: GSOState = configure(&mut context, &accounts, args).await;
+    let user_base_account: Pubkey =
+        stake(&mut context, &state, &accounts.so_base_mint, NUM_STAKE).await;
+
+    // Later stakers would pay for a slash while the subscription is open.
+    let authority: Pubkey = context.payer.pubkey();
+    let result = process(
+        &mut context,
+        &[instruction::propose_slash(
+            &state,
+            &authority,
+            0,
+            NUM_STAKE / 10,
+        )],
+        &[],
+    )
+    .await;
+    assert_gso_error(result, ErrorCode::SlashDuringSubscription);
+
+    warp_to_timestamp(&mut context, subscription_period_end + 1).await;
+    process(
+        &mut context,
+        &[instruction::propose_slash(
+            &state,
+            &authority,
+            0,
+            NUM_STAKE / 10,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    let result = process(
+        &mut context,
+        &[instruction::execute_slash(&state, &authority, 0)],
+        &[],
+    )
+    .await;
+    assert_gso_error(result, ErrorCode::SlashTimelocked);
+
+    warp_to_timestamp(
+        &mut context,
+        subscription_period_end + 1 + gso::MIN_SLASH_TIMELOCK,
+    )
+    .await;
+    process(
+        &mut context,
+        &[instruction::execute_slash(&state, &authority, 0)],
+        &[],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        token_balance(&mut context, &accounts.so_base_account).await,
+        NUM_STAKE / 10
+    );
+
+    // Stakers share what is left.
+    warp_to_timestamp(&mut context, state.lockup_period_end + 1).await;
+    unstake(&mut context, &state, &user_base_account, NUM_STAKE)
+        .await
+        .unwrap();
+    assert_eq!(
+        token_balance(&mut context, &user_base_account).await,
+        NUM_STAKE - NUM_STAKE / 10
+    );
+}
+
+#[tokio::test]
+async fn test_rollover_into_next_period() {
+    let mut context = common::start().await;
+    let accounts = create_config_accounts(&mut context).await;
+    let subscription_period_end: u64 = now(&mut context).await + SUBSCRIPTION_SECONDS;
+    let mut args = config_args("ROLLOVER", subscription_period_end);
+    args.option_expiration = subscription_period_end + 2 * SUBSCRIPTION_SECONDS;
+    let old_state: GSOState = configure(&mut context, &accounts, args).await;
+    let user_base_account: Pubkey =
+        stake(&mut context, &old_state, &accounts.so_base_mint, NUM_STAKE).await;
+
+    // The next period has the same option base mint and is configured while
+    // the options of the first one are still live.
+    let now_ts: u64 = now(&mut context).await;
+    warp_to_timestamp(&mut context, now_ts).await;
+    mint_to(
+        &mut context,
+        &accounts.so_base_mint,
+        &accounts.so_base_account,
+        NUM_TOKENS,
+    )
+    .await;
+    let mut args = config_args("ROLLOVER", subscription_period_end + SUBSCRIPTION_SECONDS);
+    args.period_num = 2;
+    process(&mut context, &[instruction::config(&accounts, args)], &[])
+        .await
+        .unwrap();
+    let address: Pubkey = find_gso_state_address(2, "ROLLOVER").0;
+    let state: GSOState = get_gso_state(&mut context, &address).await;
+    assert_eq!(state.staking_options_name(), "GSOROLLOVER-2");
+    assert_ne!(state.staking_options_state, old_state.staking_options_state);
+
+    create_associated_token_account(
+        &mut context,
+        &find_so_option_mint_address(&state.staking_options_state, state.strike).0,
+    )
+    .await;
+    create_associated_token_account(&mut context, &find_x_base_mint_address(&address, 0).0).await;
+    warp_to_timestamp(&mut context, subscription_period_end + 1).await;
+
+    // Burning xTokens of the new period instead of the old one.
+    let mut wrong_x_base_account = instruction::rollover(
+        &old_state,
+        &state,
+        &accounts.authority,
+        &user_base_account,
+        NUM_STAKE,
+        0,
+        0,
+        0,
+    );
+    wrong_x_base_account.accounts[3].pubkey = user_x_base_account(&accounts.authority, &address);
+    let result = process(&mut context, &[wrong_x_base_account], &[]).await;
+    assert_gso_error(result, ErrorCode::InvalidTokenMint);
+
+    process(
+        &mut context,
+        &[instruction::rollover(
+            &old_state,
+            &state,
+            &accounts.authority,
+            &user_base_account,
+            NUM_STAKE,
+            0,
+            0,
+            0,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+
+    let old_address: Pubkey = find_gso_state_address(1, "ROLLOVER").0;
+    assert_eq!(
+        token_balance(&mut context, &find_base_vault_address(&old_address, 0).0).await,
+        0
+    );
+    assert_eq!(
+        token_balance(&mut context, &find_base_vault_address(&address, 0).0).await,
+        NUM_STAKE
+    );
+    assert_eq!(
+        token_balance(
+            &mut context,
+            &user_x_base_account(&accounts.authority, &address)
+        )
+        .await,
+        NUM_STAKE
+    );
+    assert_eq!(
+        token_balance(
+            &mut context,
+            &so_user_option_account(&accounts.authority, &state)
+        )
+        .await,
+        NUM_STAKE * LOCKUP_RATIO_TOKENS_PER_MILLION / 1_000_000
+    );
+}
+
+#[tokio::test]
+async fn test_withdraw() {
+    let mut context = common::start().await;
+    let accounts = create_config_accounts(&mut context).await;
+    let subscription_period_end: u64 = now(&mut context).await + SUBSCRIPTION_SECONDS;
+    let state: GSOState = configure(
+        &mut context,
+        &accounts,
+        config_args("WITHDRAW", subscription_period_end),
+    )
+    .await;
+    stake(&mut context, &state, &accounts.so_base_mint, NUM_STAKE).await;
+
+    warp_to_timestamp(&mut context, subscription_period_end + 1).await;
+    let authority: Pubkey = context.payer.pubkey();
+    process(
+        &mut context,
+        &[instruction::withdraw(
+            &state,
+            &authority,
+            &accounts.so_base_account,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+
+    // Nothing was exercised so all the tokens come back.
+    assert_eq!(
+        token_balance(&mut context, &accounts.so_base_account).await,
+        NUM_TOKENS
+    );
+}
+
+#[tokio::test]
+async fn test_close_before_withdraw_fails() {
+    let mut context = common::start().await;
+    let accounts = create_config_accounts(&mut context).await;
+    let subscription_period_end: u64 = now(&mut context).await + SUBSCRIPTION_SECONDS;
+    let state: GSOState = configure(
+        &mut context,
+        &accounts,
+        config_args("CLOSE", subscription_period_end),
+    )
+    .await;
+
+    // Nothing is staked, but the SO vault still holds every unsold token.
+    warp_to_timestamp(&mut context, subscription_period_end + 1).await;
+    let authority: Pubkey = context.payer.pubkey();
+    let result = process(&mut context, &[instruction::close(&state, &authority)], &[]).await;
+    assert_gso_error(result, ErrorCode::OptionsNotWithdrawn);
+
+    process(
+        &mut context,
+        &[
+            instruction::withdraw(&state, &authority, &accounts.so_base_account),
+            instruction::close(&state, &authority),
+        ],
+        &[],
+    )
+    .await
+    .unwrap();
+    let address: Pubkey = find_gso_state_address(1, "CLOSE").0;
+    assert!(get_account(&mut context, &address).await.is_none());
+}
+
+#[tokio::test]
+async fn test_set_pause_undefined_flag_fails() {
+    let mut context = common::start().await;
+    let accounts = create_config_accounts(&mut context).await;
+    let subscription_period_end: u64 = now(&mut context).await + SUBSCRIPTION_SECONDS;
+    let state: GSOState = configure(
+        &mut context,
+        &accounts,
+        config_args("PAUSE", subscription_period_end),
+    )
+    .await;
+
+    let authority: Pubkey = context.payer.pubkey();
+    let result = process(
+        &mut context,
+        &[instruction::set_pause(&state, &authority, 1 << 3)],
+        &[],
+    )
+    .await;
+    assert_gso_error(result, ErrorCode::InvalidPauseFlags);
+
+    process(
+        &mut context,
+        &[instruction::set_pause(
+            &state,
+            &authority,
+            gso::PAUSE_STAKE | gso::PAUSE_WITHDRAW,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    let address: Pubkey = find_gso_state_address(1, "PAUSE").0;
+    assert_eq!(
+        get_gso_state(&mut context, &address).await.paused,
+        gso::PAUSE_STAKE | gso::PAUSE_WITHDRAW
+    );
+}
+
+#[tokio::test]
+async fn test_name_tokens() {
+    let mut context = common::start().await;
+    let accounts = create_config_accounts(&mut context).await;
+    let subscription_period_end: u64 = now(&mut context).await + SUBSCRIPTION_SECONDS;
+    let state: GSOState = configure(
+        &mut context,
+        &accounts,
+        config_args("NAME", subscription_period_end),
+    )
+    .await;
+
+    let authority: Pubkey = context.payer.pubkey();
+    process(
+        &mut context,
+        &[instruction::name_tokens(&state, &authority)],
+        &[],
+    )
+    .await
+    .unwrap();
+
+    let address: Pubkey = find_gso_state_address(1, "NAME").0;
+    let option_mint: Pubkey =
+        find_so_option_mint_address(&state.staking_options_state, state.strike).0;
+    assert_eq!(
+        metadata_name(&mut context, &option_mint).await,
+        "DUAL-GSONAME-1-5.00e4"
+    );
+    let x_base_mint: Pubkey = find_x_base_mint_address(&address, 0).0;
+    assert_eq!(
+        metadata_name(&mut context, &x_base_mint).await,
+        "DUAL-GSO-NAME"
+    );
+
+    // Strikes and tiers added later are named by running it again.
+    process(
+        &mut context,
+        &[
+            instruction::add_strike(&state, &authority, 2 * STRIKE, 1_000_000),
+            instruction::add_tier(
+                &state,
+                &authority,
+                &accounts.so_base_mint,
+                1,
+                subscription_period_end + 1,
+                1_000_000,
+            ),
+        ],
+        &[],
+    )
+    .await
+    .unwrap();
+    let state: GSOState = get_gso_state(&mut context, &address).await;
+    process(
+        &mut context,
+        &[instruction::name_tokens(&state, &authority)],
+        &[],
+    )
+    .await
+    .unwrap();
+    let strike_option_mint: Pubkey =
+        find_so_option_mint_address(&state.staking_options_state, 2 * STRIKE).0;
+    assert_eq!(
+        metadata_name(&mut context, &strike_option_mint).await,
+        "DUAL-GSONAME-1-1.00e5"
+    );
+    assert_eq!(
+        metadata_name(&mut context, &find_x_base_mint_address(&address, 1).0).await,
+        "DUAL-GSO-NAME-T1"
+    );
+}
+
+fn user_x_base_account(authority: &Pubkey, gso_state: &Pubkey) -> Pubkey {
+    spl_associated_token_account::get_associated_token_address(
+        authority,
+        &find_x_base_mint_address(gso_state, 0).0,
+    )
+}
+
+fn so_user_option_account(authority: &Pubkey, state: &GSOState) -> Pubkey {
+    spl_associated_token_account::get_associated_token_address(
+        authority,
+        &find_so_option_mint_address(&state.staking_options_state, state.strike).0,
+    )
+}
+
+// Metaplex pads names to a fixed length with zeros.
+async fn metadata_name(context: &mut ProgramTestContext, mint: &Pubkey) -> String {
+    let metadata = get_account(context, &find_metadata_address(mint).0)
+        .await
+        .expect("Metadata not found");
+    Metadata::safe_deserialize(&metadata.data)
+        .unwrap()
+        .data
+        .name
+        .trim_end_matches('\0')
+        .to_string()
+}
@@ -14,7 +14,7 @@ import {
   mintToAccount,
 } from './utils/utils';
 
-const { getAccount } = require('@solana/spl-token');
+const { getAccount, getAssociatedTokenAddress } = require('@solana/spl-token');
 
 const anchor = require('@project-serum/anchor');
 
@@ -30,6 +30,8 @@ describe('gso', () => {
   const soKey = new PublicKey('4yx1NJ4Vqf2zT1oVLk4SySBhhDJXmXFt88ncm4gPxtL7');
 
   const EXPIRATION_DELAY_SEC: number = 100;
+  // Minimum the program allows, so slash tests can not wait it out.
+  const SLASH_TIMELOCK_SEC: number = 24 * 60 * 60;
   let optionExpiration: number = Date.now() / 1_000 + EXPIRATION_DELAY_SEC;
   let lockupPeriodEnd: number = Date.now() / 1_000 + EXPIRATION_DELAY_SEC;
   let subscriptionPeriodEnd: number = Date.now() / 1_000 + EXPIRATION_DELAY_SEC;
@@ -51,9 +53,230 @@ describe('gso', () => {
   let userBaseAccount: PublicKey;
   let soUserOptionAccount: PublicKey;
 
-  async function configure(lockupEndToSubscriptionPeriodOffset = 0) {
+  const periodNum: number = 1;
+
+  // Matches so_name in the program.
+  function soName(name: string, period: number = periodNum): string {
+    return `GSO${name}-${period}`;
+  }
+
+  async function soAuthorityAndBump(): Promise<[PublicKey, number]> {
+    return web3.PublicKey.findProgramAddress(
+      [
+        Buffer.from(utils.bytes.utf8.encode('gso')),
+        gsoState.toBuffer(),
+      ],
+      program.programId,
+    );
+  }
+
+  async function depositRecord(owner: PublicKey): Promise<PublicKey> {
+    const [record, _bump] = await web3.PublicKey.findProgramAddress(
+      [
+        Buffer.from(utils.bytes.utf8.encode('deposit-record')),
+        gsoState.toBuffer(),
+        owner.toBuffer(),
+      ],
+      program.programId,
+    );
+    return record;
+  }
+
+  async function project(name: string): Promise<PublicKey> {
+    const [projectAddress, _bump] = await web3.PublicKey.findProgramAddress(
+      [
+        Buffer.from(utils.bytes.utf8.encode('project')),
+        Buffer.from(utils.bytes.utf8.encode(name)),
+      ],
+      program.programId,
+    );
+    return projectAddress;
+  }
+
+  async function periodState(name: string, period: number): Promise<PublicKey> {
+    const [stateAddress, _bump] = await web3.PublicKey.findProgramAddress(
+      [
+        Buffer.from(utils.bytes.utf8.encode('GSO-state')),
+        new BN(period).toArrayLike(Buffer, 'be', 8),
+        Buffer.from(utils.bytes.utf8.encode(name)),
+      ],
+      program.programId,
+    );
+    return stateAddress;
+  }
+
+  async function initProject(name: string, latestPeriod = 0) {
+    await program.rpc.initProject(
+      name,
+      new BN(latestPeriod),
+      new BN(lockupRatioTokensPerMillionLots),
+      new BN(lotSize),
+      new BN(strikePrice),
+      {
+        accounts: {
+          authority: provider.wallet.publicKey,
+          project: await project(name),
+          latestGsoState: await periodState(name, latestPeriod),
+          nextGsoState: await periodState(name, latestPeriod + 1),
+          systemProgram: web3.SystemProgram.programId,
+        },
+      },
+    );
+  }
+
+  // Config options with every optional feature off. The keys match the
+  // ConfigOptions fields in camel case.
+  function configOptions(overrides: object = {}) {
+    return {
+      maxTotalLocked: new BN(0),
+      maxPerWallet: new BN(0),
+      subscriptionPeriodStart: new BN(0),
+      lockupRatioSchedule: 0,
+      lockupRatioEndTokensPerMillion: new BN(0),
+      lockupRatioSteps: new BN(0),
+      maxSlashBps: new BN(0),
+      slashTimelock: new BN(0),
+      slashDestination: PublicKey.default,
+      creditLimit: new BN(0),
+      vestingEnd: new BN(0),
+      earlyUnstakePenaltyBps: new BN(0),
+      earlyUnstakePenaltyDecay: false,
+      earlyUnstakePenaltyDestination: PublicKey.default,
+      lockupRatioNum: new BN(0),
+      lockupRatioDen: new BN(0),
+      ...overrides,
+    };
+  }
+
+  function tierSeeds(tier: number): Buffer[] {
+    return tier === 0 ? [] : [Buffer.from([tier])];
+  }
+
+  async function tierXBaseMint(tier: number): Promise<PublicKey> {
+    const [mint, _bump] = await web3.PublicKey.findProgramAddress(
+      [
+        Buffer.from(utils.bytes.utf8.encode('xGSO')),
+        gsoState.toBuffer(),
+        ...tierSeeds(tier),
+      ],
+      program.programId,
+    );
+    return mint;
+  }
+
+  async function tierBaseVault(tier: number): Promise<PublicKey> {
+    const [vault, _bump] = await web3.PublicKey.findProgramAddress(
+      [
+        Buffer.from(utils.bytes.utf8.encode('base-vault')),
+        gsoState.toBuffer(),
+        ...tierSeeds(tier),
+      ],
+      program.programId,
+    );
+    return vault;
+  }
+
+  async function stakeInstruction(
+    amount: number,
+    userLockupAccount: PublicKey,
+    tier = 0,
+    optionMint = soOptionMint,
+  ) {
+    const [soAuthority, _soAuthorityBump] = await soAuthorityAndBump();
+    const authority = provider.wallet.publicKey;
+    const tierXBase = await tierXBaseMint(tier);
+    return program.instruction.stake(
+      new BN(amount),
+      tier,
+      {
+        accounts: {
+          authority,
+          gsoState,
+          depositRecord: await depositRecord(authority),
+          soAuthority,
+          soOptionMint: optionMint,
+          soUserOptionAccount: await getAssociatedTokenAddress(optionMint, authority),
+          soState: await soHelper.state(soName(projectName), soBaseMint),
+          stakingOptionsProgram: STAKING_OPTIONS_PK,
+          xBaseMint: tierXBase,
+          userBaseAccount: userLockupAccount,
+          userXBaseAccount: await getAssociatedTokenAddress(tierXBase, authority),
+          baseVault: await tierBaseVault(tier),
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: web3.SystemProgram.programId,
+        },
+      },
+    );
+  }
+
+  async function unstakeInstruction(
+    amount: number,
+    userLockupAccount: PublicKey,
+    tier = 0,
+    penaltyDestination: PublicKey = undefined,
+  ) {
+    const authority = provider.wallet.publicKey;
+    const tierXBase = await tierXBaseMint(tier);
+    return program.instruction.unstake(
+      new BN(amount),
+      tier,
+      {
+        accounts: {
+          authority,
+          gsoState,
+          xBaseMint: tierXBase,
+          userBaseAccount: userLockupAccount,
+          userXBaseAccount: await getAssociatedTokenAddress(tierXBase, authority),
+          baseVault: await tierBaseVault(tier),
+          tokenProgram: TOKEN_PROGRAM_ID,
+        },
+        remainingAccounts: penaltyDestination
+          ? [{ pubkey: penaltyDestination, isWritable: true, isSigner: false }]
+          : [],
+      },
+    );
+  }
+
+  async function earlyUnstakeInstruction(
+    amount: number,
+    userLockupAccount: PublicKey,
+    tier = 0,
+    optionMint = soOptionMint,
+  ) {
+    const authority = provider.wallet.publicKey;
+    const tierXBase = await tierXBaseMint(tier);
+    return program.instruction.earlyUnstake(
+      new BN(amount),
+      tier,
+      {
+        accounts: {
+          authority,
+          gsoState,
+          soState: await soHelper.state(soName(projectName), soBaseMint),
+          soOptionMint: optionMint,
+          soUserOptionAccount: await getAssociatedTokenAddress(optionMint, authority),
+          xBaseMint: tierXBase,
+          userBaseAccount: userLockupAccount,
+          userXBaseAccount: await getAssociatedTokenAddress(tierXBase, authority),
+          baseVault: await tierBaseVault(tier),
+          tokenProgram: TOKEN_PROGRAM_ID,
+        },
+      },
+    );
+  }
+
+  async function configure(
+    lockupEndToSubscriptionPeriodOffset = 0,
+    maxTotalLocked = 0,
+    maxPerWallet = 0,
+    subscriptionPeriodStart = 0,
+    maxSlashBps = 0,
+    creditLimit = 0,
+    earlyUnstakePenaltyBps = 0,
+  ) {
     console.log('Configuring');
     projectName = `TEST_${optionExpiration.toString()}`;
+    await initProject(projectName);
 
     gsoState = await gsoHelper.state(projectName);
 
@@ -82,7 +305,7 @@ describe('gso', () => {
       provider.wallet.publicKey,
     );
 
-    soOptionMint = await soHelper.soMint(strikePrice, `GSO${projectName}`, soBaseMint);
+    soOptionMint = await soHelper.soMint(strikePrice, soName(projectName), soBaseMint);
 
     xBaseMint = await gsoHelper.xBaseMint(gsoState);
     subscriptionPeriodEnd = Date.now() / 1_000 + EXPIRATION_DELAY_SEC;
@@ -90,20 +313,51 @@ describe('gso', () => {
     optionExpiration = subscriptionPeriodEnd + lockupEndToSubscriptionPeriodOffset;
 
     console.log('Creating config instruction');
-    const configInstruction = await gsoHelper.createConfigInstruction(
-      lockupRatioTokensPerMillionLots,
-      lockupPeriodEnd,
-      optionExpiration,
-      subscriptionPeriodEnd,
-      new anchor.BN(numTokensInPeriod),
+    const [soAuthority, soAuthorityBump] = await soAuthorityAndBump();
+    const currentSoName = soName(projectName);
+    const configInstruction = program.instruction.config(
+      new BN(periodNum),
+      new BN(lockupRatioTokensPerMillionLots),
+      new BN(lockupPeriodEnd),
+      new BN(optionExpiration),
+      new BN(subscriptionPeriodEnd),
+      new BN(lotSize),
+      new BN(numTokensInPeriod),
       projectName,
-      new anchor.BN(strikePrice),
-      provider.wallet.publicKey,
-      soBaseMint,
-      soQuoteMint,
-      soBaseAccount,
-      soQuoteAccount,
-      lotSize,
+      new BN(strikePrice),
+      soAuthorityBump,
+      configOptions({
+        maxTotalLocked: new BN(maxTotalLocked),
+        maxPerWallet: new BN(maxPerWallet),
+        subscriptionPeriodStart: new BN(subscriptionPeriodStart),
+        maxSlashBps: new BN(maxSlashBps),
+        slashTimelock: new BN(maxSlashBps > 0 ? SLASH_TIMELOCK_SEC : 0),
+        slashDestination: soBaseAccount,
+        creditLimit: new BN(creditLimit),
+        earlyUnstakePenaltyBps: new BN(earlyUnstakePenaltyBps),
+        earlyUnstakePenaltyDestination: soBaseAccount,
+      }),
+      {
+        accounts: {
+          authority: provider.wallet.publicKey,
+          gsoState,
+          project: await project(projectName),
+          soAuthority,
+          soState: await soHelper.state(currentSoName, soBaseMint),
+          soBaseVault: await soHelper.baseVault(currentSoName, soBaseMint),
+          soBaseAccount,
+          soQuoteAccount,
+          soBaseMint,
+          soQuoteMint,
+          soOptionMint,
+          stakingOptionsProgram: STAKING_OPTIONS_PK,
+          xBaseMint,
+          baseVault: await gsoHelper.baseVault(gsoState),
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: web3.SystemProgram.programId,
+          rent: web3.SYSVAR_RENT_PUBKEY,
+        },
+      },
     );
 
     console.log('Sending config instruction');
@@ -145,31 +399,16 @@ describe('gso', () => {
     );
 
     console.log('Creating stake instruction');
-    const stakeInstruction = await gsoHelper.createStakeInstruction(
-      numStake,
-      projectName,
-      provider.wallet.publicKey,
-      soBaseMint,
-      userBaseAccount,
-    );
-
     const tx = new anchor.web3.Transaction();
-    tx.add(stakeInstruction);
+    tx.add(await stakeInstruction(numStake, userBaseAccount));
     await provider.send(tx);
   }
 
   async function unstake() {
     console.log('Unstaking');
 
-    const unstakeInstruction = await gsoHelper.createUnstakeInstruction(
-      numStake,
-      projectName,
-      provider.wallet.publicKey,
-      userBaseAccount,
-    );
-
     const tx = new anchor.web3.Transaction();
-    tx.add(unstakeInstruction);
+    tx.add(await unstakeInstruction(numStake, userBaseAccount));
     await provider.send(tx);
   }
 
@@ -217,7 +456,7 @@ describe('gso', () => {
       ],
       program.programId,
     );
-    const soState = await soHelper.state(`GSO${projectName}`, soBaseMint);
+    const soState = await soHelper.state(soName(projectName), soBaseMint);
 
     await program.rpc.nameTokens(
       {
@@ -297,6 +536,33 @@ describe('gso', () => {
     assert.equal(Number(soBaseAccountAccount.amount), numTokensInPeriod);
   });
 
+  it('Close', async () => {
+    console.log('Closing GSO');
+    const baseVault = await gsoHelper.baseVault(gsoState);
+
+    // Already unstaked everything and waited for the lockup to end.
+    try {
+      await program.rpc.close(
+        {
+          accounts: {
+            authority: provider.wallet.publicKey,
+            gsoState,
+            xBaseMint,
+            baseVault,
+            soState: await soHelper.state(soName(projectName), soBaseMint),
+            soBaseVault: await soHelper.baseVault(soName(projectName), soBaseMint),
+            tokenProgram: TOKEN_PROGRAM_ID,
+          },
+        },
+      );
+    } catch (err) {
+      console.log(err);
+      assert(false);
+    }
+    assert.equal(await provider.connection.getAccountInfo(gsoState), null);
+    assert.equal(await provider.connection.getAccountInfo(baseVault), null);
+  });
+
   it('UnstakeBeforeUnlockFail', async () => {
     await configure();
     await stake();
@@ -359,109 +625,728 @@ describe('gso', () => {
     assert.equal(xNft.name, `DUAL-GSO-${projectName}`.substring(0, 24));
   });
 
-  it('ConfigV2e2e', async () => {
-    console.log('Configuring V2');
-    projectName = `TEST_${optionExpiration.toString()}`;
+  it('PauseStake', async () => {
+    await configure();
+    const PAUSE_STAKE = 1;
 
-    gsoState = await gsoHelper.state(projectName);
-    soBaseMint = await createMint(provider, undefined);
-    soQuoteMint = await createMint(provider, undefined);
+    async function setPause(paused: number) {
+      await program.rpc.setPause(
+        paused,
+        {
+          accounts: {
+            authority: provider.wallet.publicKey,
+            gsoState,
+          },
+        },
+      );
+    }
 
-    console.log('Creating base account');
-    soBaseAccount = await createAssociatedTokenAccount(
-      provider,
-      soBaseMint,
-      provider.wallet.publicKey,
-    );
-    console.log('Minting base tokens');
-    await mintToAccount(
-      provider,
-      soBaseMint,
-      soBaseAccount,
-      new anchor.BN(numTokensInPeriod),
-      provider.wallet.publicKey,
-    );
+    await setPause(PAUSE_STAKE);
+    try {
+      await stake();
+      assert(false);
+    } catch (err) {
+      console.log(err);
+    }
 
-    console.log('Creating quote account');
-    soQuoteAccount = await createAssociatedTokenAccount(
-      provider,
-      soQuoteMint,
-      provider.wallet.publicKey,
-    );
+    // Accounts were created in the failed stake, so only retry the instruction.
+    await setPause(0);
+    try {
+      const tx = new anchor.web3.Transaction();
+      tx.add(await stakeInstruction(numStake, userBaseAccount));
+      await provider.send(tx);
+    } catch (err) {
+      console.log(err);
+      assert(false);
+    }
+  });
 
-    console.log('Creating config v2 instruction');
+  it('StakeOverWalletCapFail', async () => {
+    await configure(0, 0, numStake / 2);
+    try {
+      await stake();
+      assert(false);
+    } catch (err) {
+      console.log(err);
+    }
 
-    const lockupRatioPerMillionLots = lockupRatioTokensPerMillionLots;
-    const numTokensAtoms = new anchor.BN(numTokensInPeriod);
-    const strikeAtomsPerLot = new anchor.BN(strikePrice);
-    const authority = provider.wallet.publicKey;
+    // Staking up to the cap is fine.
+    try {
+      const tx = new anchor.web3.Transaction();
+      tx.add(await stakeInstruction(numStake / 2, userBaseAccount));
+      await provider.send(tx);
+    } catch (err) {
+      console.log(err);
+      assert(false);
+    }
+    const state = await program.account.gsoState.fetch(gsoState);
+    assert.equal(state.totalLocked.toNumber(), numStake / 2);
+  });
 
-    xBaseMint = await gsoHelper.xBaseMint(gsoState);
-    subscriptionPeriodEnd = Date.now() / 1_000 + EXPIRATION_DELAY_SEC;
-    lockupPeriodEnd = subscriptionPeriodEnd;
-    optionExpiration = subscriptionPeriodEnd;
+  it('StakeBeforeSubscriptionStartFail', async () => {
+    await configure(0, 0, 0, Date.now() / 1_000 + EXPIRATION_DELAY_SEC / 2);
+    try {
+      await stake();
+      assert(false);
+    } catch (err) {
+      console.log(err);
+    }
+  });
 
-    console.log('Creating config instruction');
+  it('StakeTooSmallFail', async () => {
+    await configure();
+    await stake();
 
-    const [soAuthority, soAuthorityBump] = await web3.PublicKey.findProgramAddress(
-      [
-        Buffer.from(utils.bytes.utf8.encode('gso')),
-        gsoState.toBuffer(),
-      ],
-      program.programId,
+    // Not enough for a single lot of options.
+    await mintToAccount(
+      provider,
+      soBaseMint,
+      userBaseAccount,
+      new anchor.BN(1),
+      provider.wallet.publicKey,
     );
-    const so = new StakingOptions(provider.connection.rpcEndpoint);
-
-    const soState = await so.state(`GSO${projectName}`, soBaseMint);
-    const soBaseVault = await so.baseVault(`GSO${projectName}`, soBaseMint);
-    soOptionMint = await so.soMint(strikeAtomsPerLot, `GSO${projectName}`, soBaseMint);
-    const baseVault = await gsoHelper.baseVault(gsoState);
+    try {
+      const tx = new anchor.web3.Transaction();
+      tx.add(await stakeInstruction(1, userBaseAccount));
+      await provider.send(tx);
+      assert(false);
+    } catch (err) {
+      console.log(err);
+    }
+  });
 
-    console.log('Sending config instruction');
-    const lockupMint = await createMint(provider, undefined);
+  it('StakeTier', async () => {
+    await configure();
+    await stake();
 
+    // A second tier that unlocks well after tier 0 and earns twice as much.
+    const tier = 1;
+    const tierXBase = await tierXBaseMint(tier);
     try {
-      await program.rpc.configV2(
-        new BN(1), /* period_num */
-        new BN(lockupRatioPerMillionLots),
-        new BN(lockupPeriodEnd),
-        new BN(optionExpiration),
-        new BN(subscriptionPeriodEnd),
-        new BN(lotSize),
-        numTokensAtoms,
-        projectName,
-        new BN(strikeAtomsPerLot),
-        soAuthorityBump,
+      await program.rpc.addTier(
+        tier,
+        new BN(lockupPeriodEnd + 10 * EXPIRATION_DELAY_SEC),
+        new BN(2 * lockupRatioTokensPerMillionLots),
         {
           accounts: {
-            authority,
+            authority: provider.wallet.publicKey,
             gsoState,
-            soAuthority,
-            soState,
-            soBaseVault,
-            soBaseAccount,
-            soQuoteAccount,
-            soBaseMint,
-            soQuoteMint,
-            soOptionMint,
-            xBaseMint,
-            baseVault,
-            lockupMint,
-            stakingOptionsProgram: STAKING_OPTIONS_PK,
+            tierZeroBaseVault: await gsoHelper.baseVault(gsoState),
+            lockupMint: soBaseMint,
+            xBaseMint: tierXBase,
+            baseVault: await tierBaseVault(tier),
             tokenProgram: TOKEN_PROGRAM_ID,
             systemProgram: web3.SystemProgram.programId,
             rent: web3.SYSVAR_RENT_PUBKEY,
           },
         },
       );
-      console.log('Config success');
     } catch (err) {
       console.log(err);
       assert(false);
     }
 
-    /// // Stake
-    console.log('Staking');
+    await createAssociatedTokenAccount(provider, tierXBase, provider.wallet.publicKey);
+    await mintToAccount(
+      provider,
+      soBaseMint,
+      userBaseAccount,
+      new anchor.BN(numStake),
+      provider.wallet.publicKey,
+    );
+    try {
+      const tx = new anchor.web3.Transaction();
+      tx.add(await stakeInstruction(numStake, userBaseAccount, tier));
+      await provider.send(tx);
+    } catch (err) {
+      console.log(err);
+      assert(false);
+    }
+    const soUserOptionAccountAccount = await getAccount(provider.connection, soUserOptionAccount);
+    assert.equal(
+      Number(soUserOptionAccountAccount.amount),
+      3 * numStake * (lockupRatioTokensPerMillionLots / 1_000_000),
+    );
+
+    // Tier 0 unlocks, but tier 1 is still locked.
+    await new Promise((r) => setTimeout(r, EXPIRATION_DELAY_SEC * 1_000));
+    try {
+      await unstake();
+    } catch (err) {
+      console.log(err);
+      assert(false);
+    }
+    try {
+      const tx = new anchor.web3.Transaction();
+      tx.add(await unstakeInstruction(numStake, userBaseAccount, tier));
+      await provider.send(tx);
+      assert(false);
+    } catch (err) {
+      console.log(err);
+    }
+  });
+
+  it('StakeStrikeLadder', async () => {
+    await configure();
+    await stake();
+
+    // A higher strike that earns twice as many options.
+    const [soAuthority, _soAuthorityBump] = await soAuthorityAndBump();
+    const currentSoName = soName(projectName);
+    const higherStrike = 2 * strikePrice;
+    const higherOptionMint = await soHelper.soMint(higherStrike, currentSoName, soBaseMint);
+    try {
+      await program.rpc.addStrike(
+        new BN(higherStrike),
+        new BN(2 * lockupRatioTokensPerMillionLots),
+        {
+          accounts: {
+            authority: provider.wallet.publicKey,
+            gsoState,
+            soAuthority,
+            soState: await soHelper.state(currentSoName, soBaseMint),
+            soOptionMint: higherOptionMint,
+            stakingOptionsProgram: STAKING_OPTIONS_PK,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            systemProgram: web3.SystemProgram.programId,
+            rent: web3.SYSVAR_RENT_PUBKEY,
+          },
+        },
+      );
+    } catch (err) {
+      console.log(err);
+      assert(false);
+    }
+
+    const higherOptionAccount = await createAssociatedTokenAccount(
+      provider,
+      higherOptionMint,
+      provider.wallet.publicKey,
+    );
+    await mintToAccount(
+      provider,
+      soBaseMint,
+      userBaseAccount,
+      new anchor.BN(numStake),
+      provider.wallet.publicKey,
+    );
+    try {
+      const tx = new anchor.web3.Transaction();
+      tx.add(await stakeInstruction(numStake, userBaseAccount, 0, 1, higherOptionMint));
+      await provider.send(tx);
+    } catch (err) {
+      console.log(err);
+      assert(false);
+    }
+    const higherOptionAccountAccount = await getAccount(provider.connection, higherOptionAccount);
+    assert.equal(
+      Number(higherOptionAccountAccount.amount),
+      2 * numStake * (lockupRatioTokensPerMillionLots / 1_000_000),
+    );
+  });
+
+  it('SlashTimelocked', async () => {
+    // Up to 10% can be slashed.
+    await configure(0, 0, 0, 0, 1_000);
+    await stake();
+
+    const slashAmount = numStake / 10;
+    const baseVault = await gsoHelper.baseVault(gsoState);
+    // Not while the subscription is open.
+    try {
+      await program.rpc.proposeSlash(
+        0,
+        new BN(slashAmount),
+        {
+          accounts: {
+            authority: provider.wallet.publicKey,
+            gsoState,
+            baseVault,
+          },
+        },
+      );
+      assert(false);
+    } catch (err) {
+      console.log(err);
+    }
+
+    await new Promise((r) => setTimeout(r, EXPIRATION_DELAY_SEC * 1_000));
+    try {
+      await program.rpc.proposeSlash(
+        0,
+        new BN(slashAmount),
+        {
+          accounts: {
+            authority: provider.wallet.publicKey,
+            gsoState,
+            baseVault,
+          },
+        },
+      );
+    } catch (err) {
+      console.log(err);
+      assert(false);
+    }
+
+    // The timelock is a day, so the slash can not execute yet. Executing and
+    // unstaking pro-rata is covered by the native tests, which can warp.
+    try {
+      await program.rpc.executeSlash(
+        0,
+        {
+          accounts: {
+            authority: provider.wallet.publicKey,
+            gsoState,
+            baseVault,
+            slashDestination: soBaseAccount,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          },
+        },
+      );
+      assert(false);
+    } catch (err) {
+      console.log(err);
+    }
+
+    // Anything more is over the cap.
+    try {
+      await program.rpc.proposeSlash(
+        0,
+        new BN(slashAmount + 1),
+        {
+          accounts: {
+            authority: provider.wallet.publicKey,
+            gsoState,
+            baseVault,
+          },
+        },
+      );
+      assert(false);
+    } catch (err) {
+      console.log(err);
+    }
+  });
+
+  it('BorrowAndRepay', async () => {
+    const creditLimit = numStake / 2;
+    await configure(0, 0, 0, 0, 0, creditLimit);
+    await stake();
+
+    const baseVault = await gsoHelper.baseVault(gsoState);
+    async function borrow(amount: number) {
+      await program.rpc.borrow(
+        new BN(amount),
+        {
+          accounts: {
+            authority: provider.wallet.publicKey,
+            gsoState,
+            baseVault,
+            destination: soBaseAccount,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          },
+        },
+      );
+    }
+
+    try {
+      await borrow(creditLimit);
+    } catch (err) {
+      console.log(err);
+      assert(false);
+    }
+    try {
+      await borrow(1);
+      assert(false);
+    } catch (err) {
+      console.log(err);
+    }
+
+    try {
+      await program.rpc.repay(
+        new BN(creditLimit),
+        {
+          accounts: {
+            authority: provider.wallet.publicKey,
+            gsoState,
+            baseVault,
+            source: soBaseAccount,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          },
+        },
+      );
+    } catch (err) {
+      console.log(err);
+      assert(false);
+    }
+    const state = await program.account.gsoState.fetch(gsoState);
+    assert.equal(state.outstandingDebt.toNumber(), 0);
+
+    await new Promise((r) => setTimeout(r, EXPIRATION_DELAY_SEC * 1_000));
+    try {
+      await unstake();
+    } catch (err) {
+      console.log(err);
+      assert(false);
+    }
+  });
+
+  it('EarlyUnstake', async () => {
+    await configure();
+    await stake();
+
+    try {
+      const tx = new anchor.web3.Transaction();
+      tx.add(await earlyUnstakeInstruction(numStake, userBaseAccount));
+      await provider.send(tx);
+    } catch (err) {
+      console.log(err);
+      assert(false);
+    }
+    const userBaseAccountAccount = await getAccount(provider.connection, userBaseAccount);
+    assert.equal(userBaseAccountAccount.amount, numStake);
+    const soUserOptionAccountAccount = await getAccount(provider.connection, soUserOptionAccount);
+    assert.equal(soUserOptionAccountAccount.amount, 0);
+  });
+
+  it('UnstakeEarlyWithPenalty', async () => {
+    const penaltyBps = 1_000;
+    await configure(0, 0, 0, 0, 0, 0, penaltyBps);
+    await stake();
+
+    try {
+      const tx = new anchor.web3.Transaction();
+      tx.add(await unstakeInstruction(numStake, userBaseAccount, 0, soBaseAccount));
+      await provider.send(tx);
+    } catch (err) {
+      console.log(err);
+      assert(false);
+    }
+    const penalty = Math.ceil((numStake * penaltyBps) / 10_000);
+    const userBaseAccountAccount = await getAccount(provider.connection, userBaseAccount);
+    assert.equal(userBaseAccountAccount.amount, numStake - penalty);
+    const soBaseAccountAccount = await getAccount(provider.connection, soBaseAccount);
+    assert.equal(soBaseAccountAccount.amount, penalty);
+  });
+
+  it('Rollover', async () => {
+    await configure();
+    await stake();
+    const oldGsoState = gsoState;
+    const oldXBaseMint = xBaseMint;
+    const oldBaseVault = await gsoHelper.baseVault(gsoState);
+    const lockupMint = soBaseMint;
+    const authority = provider.wallet.publicKey;
+
+    console.log('Waiting for the first period to end');
+    await new Promise((r) => setTimeout(r, EXPIRATION_DELAY_SEC * 1_000));
+
+    // The next period has the same option base mint and lockup mint. The
+    // period is in the SO name, so it gets its own SO state.
+    [gsoState] = await web3.PublicKey.findProgramAddress(
+      [
+        Buffer.from(utils.bytes.utf8.encode('GSO-state')),
+        new BN(periodNum + 1).toArrayLike(Buffer, 'be', 8),
+        Buffer.from(utils.bytes.utf8.encode(projectName)),
+      ],
+      program.programId,
+    );
+    await mintToAccount(
+      provider,
+      soBaseMint,
+      soBaseAccount,
+      new anchor.BN(numTokensInPeriod),
+      authority,
+    );
+    const nextSoName = soName(projectName, periodNum + 1);
+    soOptionMint = await soHelper.soMint(strikePrice, nextSoName, soBaseMint);
+    xBaseMint = await gsoHelper.xBaseMint(gsoState);
+    subscriptionPeriodEnd = Date.now() / 1_000 + EXPIRATION_DELAY_SEC;
+    const [soAuthority, soAuthorityBump] = await soAuthorityAndBump();
+
+    await program.rpc.configV2(
+      new BN(periodNum + 1),
+      new BN(lockupRatioTokensPerMillionLots),
+      new BN(subscriptionPeriodEnd),
+      new BN(subscriptionPeriodEnd),
+      new BN(subscriptionPeriodEnd),
+      new BN(lotSize),
+      new BN(numTokensInPeriod),
+      projectName,
+      new BN(strikePrice),
+      soAuthorityBump,
+      configOptions(),
+      {
+        accounts: {
+          authority,
+          gsoState,
+          project: await project(projectName),
+          soAuthority,
+          soState: await soHelper.state(nextSoName, soBaseMint),
+          soBaseVault: await soHelper.baseVault(nextSoName, soBaseMint),
+          soBaseAccount,
+          soQuoteAccount,
+          soBaseMint,
+          soQuoteMint,
+          soOptionMint,
+          xBaseMint,
+          baseVault: await gsoHelper.baseVault(gsoState),
+          lockupMint,
+          stakingOptionsProgram: STAKING_OPTIONS_PK,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: web3.SystemProgram.programId,
+          rent: web3.SYSVAR_RENT_PUBKEY,
+        },
+      },
+    );
+
+    soUserOptionAccount = await createAssociatedTokenAccount(provider, soOptionMint, authority);
+    await createAssociatedTokenAccount(provider, xBaseMint, authority);
+
+    try {
+      await program.rpc.rollover(
+        new BN(numStake),
+        0, /* old_tier */
+        0, /* tier */
+        0, /* strike_index */
+        {
+          accounts: {
+            authority,
+            oldGsoState,
+            oldXBaseMint,
+            oldUserXBaseAccount: await getAssociatedTokenAddress(oldXBaseMint, authority),
+            oldBaseVault,
+            userBaseAccount,
+            gsoState,
+            depositRecord: await depositRecord(authority),
+            soAuthority,
+            soOptionMint,
+            soUserOptionAccount,
+            soState: await soHelper.state(nextSoName, soBaseMint),
+            stakingOptionsProgram: STAKING_OPTIONS_PK,
+            xBaseMint,
+            userXBaseAccount: await getAssociatedTokenAddress(xBaseMint, authority),
+            baseVault: await gsoHelper.baseVault(gsoState),
+            tokenProgram: TOKEN_PROGRAM_ID,
+            systemProgram: web3.SystemProgram.programId,
+          },
+        },
+      );
+    } catch (err) {
+      console.log(err);
+      assert(false);
+    }
+
+    const oldBaseVaultAccount = await getAccount(provider.connection, oldBaseVault);
+    assert.equal(oldBaseVaultAccount.amount, 0);
+    const baseVaultAccount = await getAccount(
+      provider.connection,
+      await gsoHelper.baseVault(gsoState),
+    );
+    assert.equal(baseVaultAccount.amount, numStake);
+    const soUserOptionAccountAccount = await getAccount(provider.connection, soUserOptionAccount);
+    assert.equal(
+      soUserOptionAccountAccount.amount,
+      numStake * (lockupRatioTokensPerMillionLots / 1_000_000),
+    );
+  });
+
+  it('Project', async () => {
+    await configure();
+
+    const projectAccount = await program.account.project.fetch(await project(projectName));
+    assert.equal(projectAccount.latestPeriod.toNumber(), periodNum);
+    assert.equal(projectAccount.authority.toBase58(), provider.wallet.publicKey.toBase58());
+
+    // The name is already taken.
+    try {
+      await initProject(projectName);
+      assert(false);
+    } catch (err) {
+      console.log(err);
+    }
+  });
+
+  it('StakedEvent', async () => {
+    await configure();
+
+    let stakedEvent;
+    const listener = program.addEventListener('Staked', (event) => {
+      stakedEvent = event;
+    });
+    await stake();
+    await new Promise((r) => setTimeout(r, 2_000));
+    await program.removeEventListener(listener);
+
+    assert.equal(stakedEvent.gsoState.toBase58(), gsoState.toBase58());
+    assert.equal(stakedEvent.user.toBase58(), provider.wallet.publicKey.toBase58());
+    assert.equal(stakedEvent.amount.toNumber(), numStake);
+    assert.equal(
+      stakedEvent.optionsIssued.toNumber(),
+      numStake * (lockupRatioTokensPerMillionLots / 1_000_000),
+    );
+    assert.equal(
+      stakedEvent.lockupRatioTokensPerMillion.toNumber(),
+      lockupRatioTokensPerMillionLots,
+    );
+    assert.equal(stakedEvent.lockupRatioNum.toNumber(), lockupRatioTokensPerMillionLots);
+    assert.equal(stakedEvent.lockupRatioDen.toNumber(), 1_000_000);
+  });
+
+  it('TransferAuthority', async () => {
+    await configure();
+    const newAuthority = web3.Keypair.generate();
+
+    try {
+      await program.rpc.proposeAuthority(
+        newAuthority.publicKey,
+        {
+          accounts: {
+            authority: provider.wallet.publicKey,
+            gsoState,
+          },
+        },
+      );
+      await program.rpc.acceptAuthority(
+        {
+          accounts: {
+            pendingAuthority: newAuthority.publicKey,
+            gsoState,
+          },
+          signers: [newAuthority],
+        },
+      );
+    } catch (err) {
+      console.log(err);
+      assert(false);
+    }
+
+    const state = await program.account.gsoState.fetch(gsoState);
+    assert.equal(state.authority.toBase58(), newAuthority.publicKey.toBase58());
+    assert.equal(state.pendingAuthority.toBase58(), PublicKey.default.toBase58());
+
+    // The old authority can no longer propose.
+    try {
+      await program.rpc.proposeAuthority(
+        provider.wallet.publicKey,
+        {
+          accounts: {
+            authority: provider.wallet.publicKey,
+            gsoState,
+          },
+        },
+      );
+      assert(false);
+    } catch (err) {
+      console.log(err);
+    }
+  });
+
+  it('ConfigV2e2e', async () => {
+    console.log('Configuring V2');
+    projectName = `TEST_${optionExpiration.toString()}`;
+    await initProject(projectName);
+
+    gsoState = await gsoHelper.state(projectName);
+    soBaseMint = await createMint(provider, undefined);
+    soQuoteMint = await createMint(provider, undefined);
+
+    console.log('Creating base account');
+    soBaseAccount = await createAssociatedTokenAccount(
+      provider,
+      soBaseMint,
+      provider.wallet.publicKey,
+    );
+    console.log('Minting base tokens');
+    await mintToAccount(
+      provider,
+      soBaseMint,
+      soBaseAccount,
+      new anchor.BN(numTokensInPeriod),
+      provider.wallet.publicKey,
+    );
+
+    console.log('Creating quote account');
+    soQuoteAccount = await createAssociatedTokenAccount(
+      provider,
+      soQuoteMint,
+      provider.wallet.publicKey,
+    );
+
+    console.log('Creating config v2 instruction');
+
+    const lockupRatioPerMillionLots = lockupRatioTokensPerMillionLots;
+    const numTokensAtoms = new anchor.BN(numTokensInPeriod);
+    const strikeAtomsPerLot = new anchor.BN(strikePrice);
+    const authority = provider.wallet.publicKey;
+
+    xBaseMint = await gsoHelper.xBaseMint(gsoState);
+    subscriptionPeriodEnd = Date.now() / 1_000 + EXPIRATION_DELAY_SEC;
+    lockupPeriodEnd = subscriptionPeriodEnd;
+    optionExpiration = subscriptionPeriodEnd;
+
+    console.log('Creating config instruction');
+
+    const [soAuthority, soAuthorityBump] = await web3.PublicKey.findProgramAddress(
+      [
+        Buffer.from(utils.bytes.utf8.encode('gso')),
+        gsoState.toBuffer(),
+      ],
+      program.programId,
+    );
+    const so = new StakingOptions(provider.connection.rpcEndpoint);
+
+    const soState = await so.state(soName(projectName), soBaseMint);
+    const soBaseVault = await so.baseVault(soName(projectName), soBaseMint);
+    soOptionMint = await so.soMint(strikeAtomsPerLot, soName(projectName), soBaseMint);
+    const baseVault = await gsoHelper.baseVault(gsoState);
+
+    console.log('Sending config instruction');
+    const lockupMint = await createMint(provider, undefined);
+
+    try {
+      await program.rpc.configV2(
+        new BN(1), /* period_num */
+        new BN(lockupRatioPerMillionLots),
+        new BN(lockupPeriodEnd),
+        new BN(optionExpiration),
+        new BN(subscriptionPeriodEnd),
+        new BN(lotSize),
+        numTokensAtoms,
+        projectName,
+        new BN(strikeAtomsPerLot),
+        soAuthorityBump,
+        configOptions(),
+        {
+          accounts: {
+            authority,
+            gsoState,
+            project: await project(projectName),
+            soAuthority,
+            soState,
+            soBaseVault,
+            soBaseAccount,
+            soQuoteAccount,
+            soBaseMint,
+            soQuoteMint,
+            soOptionMint,
+            xBaseMint,
+            baseVault,
+            lockupMint,
+            stakingOptionsProgram: STAKING_OPTIONS_PK,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            systemProgram: web3.SystemProgram.programId,
+            rent: web3.SYSVAR_RENT_PUBKEY,
+          },
+        },
+      );
+      console.log('Config success');
+    } catch (err) {
+      console.log(err);
+      assert(false);
+    }
+
+    /// // Stake
+    console.log('Staking');
 
     console.log('Creating lockup account');
     // This is another account, not the same as used before.
@@ -495,16 +1380,8 @@ describe('gso', () => {
     );
 
     console.log('Creating stake instruction');
-    const stakeInstruction = await gsoHelper.createStakeInstruction(
-      numStake,
-      projectName,
-      provider.wallet.publicKey,
-      soBaseMint,
-      userLockupAccount,
-    );
-
     const stakeTx = new anchor.web3.Transaction();
-    stakeTx.add(stakeInstruction);
+    stakeTx.add(await stakeInstruction(numStake, userLockupAccount));
     try {
       await provider.send(stakeTx);
     } catch (err) {
@@ -517,15 +1394,8 @@ describe('gso', () => {
 
     console.log('Unstaking');
 
-    const unstakeInstruction = await gsoHelper.createUnstakeInstruction(
-      numStake,
-      projectName,
-      provider.wallet.publicKey,
-      userLockupAccount,
-    );
-
     const unstakeTx = new anchor.web3.Transaction();
-    unstakeTx.add(unstakeInstruction);
+    unstakeTx.add(await unstakeInstruction(numStake, userLockupAccount));
     await provider.send(unstakeTx);
   });
 });